@@ -0,0 +1,161 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::{Finding, Severity};
+
+const BASELINE_VERSION: u32 = 1;
+
+/// A finding as recorded in the baseline file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub fingerprint: String,
+    pub rule: String,
+    pub severity: Severity,
+    pub packages: Vec<String>,
+    pub files: Vec<String>,
+    pub message: String,
+}
+
+/// Snapshot of accepted findings, keyed by fingerprint.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    pub findings: Vec<BaselineEntry>,
+}
+
+/// Result of checking the current findings against a baseline.
+pub struct BaselineComparison<'a> {
+    /// Findings whose fingerprint is not in the baseline.
+    pub new: Vec<&'a Finding>,
+    /// Baseline entries that no longer occur.
+    pub fixed: Vec<BaselineEntry>,
+}
+
+impl Baseline {
+    pub fn from_findings(findings: &[Finding], root: &Path) -> Self {
+        let mut entries = BTreeMap::new();
+        for finding in findings {
+            let fingerprint = finding.fingerprint(root);
+            entries
+                .entry(fingerprint.clone())
+                .or_insert_with(|| BaselineEntry {
+                    fingerprint,
+                    rule: finding.rule.to_string(),
+                    severity: finding.severity,
+                    packages: finding.packages.clone(),
+                    files: finding.relative_files(root),
+                    // Only keep the headline; suggestions are noise in a baseline diff
+                    message: finding.message.lines().next().unwrap_or("").to_string(),
+                });
+        }
+
+        Self {
+            version: BASELINE_VERSION,
+            findings: entries.into_values().collect(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read baseline {}", path.display()))?;
+        let baseline: Baseline = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse baseline {}", path.display()))?;
+        if baseline.version != BASELINE_VERSION {
+            anyhow::bail!(
+                "Unsupported baseline version {} in {} (expected {})",
+                baseline.version,
+                path.display(),
+                BASELINE_VERSION
+            );
+        }
+        Ok(baseline)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut content = serde_json::to_string_pretty(self)?;
+        content.push('\n');
+        fs::write(path, content)
+            .with_context(|| format!("Failed to write baseline to {}", path.display()))
+    }
+
+    pub fn compare<'a>(&self, findings: &'a [Finding], root: &Path) -> BaselineComparison<'a> {
+        let current: Vec<(String, &Finding)> = findings
+            .iter()
+            .map(|f| (f.fingerprint(root), f))
+            .collect();
+
+        let new = current
+            .iter()
+            .filter(|(fingerprint, _)| !self.findings.iter().any(|e| &e.fingerprint == fingerprint))
+            .map(|(_, finding)| *finding)
+            .collect();
+
+        let fixed = self
+            .findings
+            .iter()
+            .filter(|e| !current.iter().any(|(fingerprint, _)| fingerprint == &e.fingerprint))
+            .cloned()
+            .collect();
+
+        BaselineComparison { new, fixed }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Location, SourceSpan};
+
+    fn finding(rule: &'static str, package: &str, file: &str, message: &str) -> Finding {
+        Finding::new(
+            rule,
+            Severity::Error,
+            message.to_string(),
+            vec![package.to_string()],
+            vec![Location {
+                file: file.to_string(),
+                span: SourceSpan::default(),
+            }],
+        )
+    }
+
+    #[test]
+    fn baselined_findings_are_suppressed_and_missing_ones_reported_fixed() {
+        let root = Path::new("/repo");
+        let accepted = [
+            finding("multiple-versions", "numpy", "/repo/a/requirements.txt", "numpy: 1.0, 2.0"),
+            finding("multiple-versions", "pandas", "/repo/a/requirements.txt", "pandas"),
+        ];
+        let baseline = Baseline::from_findings(&accepted, root);
+
+        let current = [
+            // Same finding after a version bump: still suppressed
+            finding("multiple-versions", "numpy", "/repo/a/requirements.txt", "numpy: 1.1, 2.0"),
+            finding("multiple-versions", "numpy", "/repo/b/requirements.txt", "numpy: 1.0, 2.0"),
+        ];
+        let comparison = baseline.compare(&current, root);
+        assert_eq!(comparison.new.len(), 1);
+        assert_eq!(comparison.new[0].locations[0].file, "/repo/b/requirements.txt");
+        let fixed: Vec<&str> = comparison.fixed.iter().map(|e| e.packages[0].as_str()).collect();
+        assert_eq!(fixed, ["pandas"]);
+    }
+
+    #[test]
+    fn baseline_keeps_one_entry_per_fingerprint_with_the_headline_only() {
+        let root = Path::new("/repo");
+        let findings = [
+            finding("multiple-versions", "numpy", "/repo/requirements.txt", "numpy\n  Suggestion"),
+            finding("multiple-versions", "numpy", "/repo/requirements.txt", "numpy again"),
+        ];
+        let baseline = Baseline::from_findings(&findings, root);
+        assert_eq!(baseline.version, BASELINE_VERSION);
+        assert_eq!(baseline.findings.len(), 1);
+        let entry = &baseline.findings[0];
+        assert_eq!(entry.message, "numpy");
+        assert_eq!(entry.files, ["requirements.txt"]);
+        assert_eq!(entry.fingerprint, findings[0].fingerprint(root));
+    }
+}
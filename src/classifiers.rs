@@ -0,0 +1,125 @@
+//! Graph categories from the trove classifiers in package index metadata,
+//! for packages no configured category matches.
+
+use anyhow::Result;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::config::CategoryConfig;
+use crate::DependencyAnalyzer;
+
+/// Classifier prefixes and the category they put a package in, by priority.
+const TOPICS: &[(&str, &str)] = &[
+    ("Topic :: Scientific/Engineering :: Artificial Intelligence", "Machine Learning"),
+    ("Topic :: Scientific/Engineering :: Image Recognition", "Machine Learning"),
+    ("Topic :: Scientific/Engineering", "Data Processing"),
+    ("Framework :: Django", "Web"),
+    ("Framework :: Flask", "Web"),
+    ("Framework :: FastAPI", "Web"),
+    ("Topic :: Internet :: WWW/HTTP", "Web"),
+    ("Topic :: Database", "Database"),
+    ("Framework :: Pytest", "Testing"),
+    ("Topic :: Software Development :: Testing", "Testing"),
+    ("Topic :: Software Development :: Quality Assurance", "Development Tools"),
+    ("Topic :: Software Development :: Build Tools", "Development Tools"),
+    ("Topic :: Security", "Security"),
+    ("Topic :: Utilities", "Utilities"),
+];
+
+/// Index requests in flight at once, to stay polite to the index on large graphs.
+const MAX_CONCURRENT_LOOKUPS: usize = 8;
+
+#[derive(Deserialize)]
+struct Metadata {
+    info: Info,
+}
+
+#[derive(Deserialize)]
+struct Info {
+    #[serde(default)]
+    classifiers: Vec<String>,
+}
+
+/// Category of the highest-priority topic among `classifiers`.
+fn category_for(classifiers: &[String]) -> Option<&'static str> {
+    TOPICS
+        .iter()
+        .find(|(prefix, _)| classifiers.iter().any(|c| c.starts_with(prefix)))
+        .map(|(_, category)| *category)
+}
+
+/// Fetch the classifiers of every uncategorized package in the graph from
+/// the configured index and add the package to the category they point at,
+/// creating it after the configured ones if needed. Returns the packages
+/// whose metadata could not be fetched; they stay in "Other".
+pub async fn classify(analyzer: &mut DependencyAnalyzer) -> Result<Vec<String>> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .user_agent(concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    let index = analyzer.config.index.url.trim_end_matches('/').to_string();
+
+    let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_LOOKUPS));
+    let mut lookups = JoinSet::new();
+    for name in analyzer.graph.node_weights() {
+        if analyzer.local_packages.contains(name) || analyzer.category_index(name).is_some() {
+            continue;
+        }
+        let client = client.clone();
+        let permits = Arc::clone(&permits);
+        let url = format!("{}/{}/json", index, name);
+        let name = name.clone();
+        lookups.spawn(async move {
+            let metadata = async {
+                let _permit = permits.acquire().await.expect("semaphore is open");
+                client
+                    .get(&url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<Metadata>()
+                    .await
+            };
+            (name, metadata.await)
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(result) = lookups.join_next().await {
+        results.push(result?);
+    }
+    // Lookups finish in any order; sort so categories are created stably
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut failed = Vec::new();
+    let mut found: Vec<(usize, String)> = Vec::new();
+    for (name, metadata) in results {
+        match metadata {
+            Ok(metadata) => {
+                if let Some(category) = category_for(&metadata.info.classifiers) {
+                    let priority = TOPICS.iter().position(|(_, c)| *c == category).unwrap_or(0);
+                    found.push((priority, name));
+                }
+            }
+            Err(_) => failed.push(name),
+        }
+    }
+    found.sort();
+    for (priority, name) in found {
+        let category = TOPICS[priority].1;
+        let categories = &mut analyzer.config.categories;
+        match categories.iter_mut().find(|c| c.name == category) {
+            Some(existing) => existing.packages.push(name),
+            None => categories.push(CategoryConfig {
+                name: category.to_string(),
+                packages: vec![name],
+                color: None,
+            }),
+        }
+    }
+    analyzer.load_categories()?;
+    Ok(failed)
+}
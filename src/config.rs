@@ -0,0 +1,265 @@
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::lockfile::normalize_name;
+use crate::vfs::FileSystem;
+use crate::Severity;
+
+/// Name of the standalone configuration file looked up in the scan root.
+pub const CONFIG_FILE_NAME: &str = ".dependency-analyzer.toml";
+
+/// Settings read from `.dependency-analyzer.toml` or `[tool.dependency-analyzer]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// Globs (relative to the scan root) a dependency file must match to be scanned
+    pub include: Vec<String>,
+    /// Globs (relative to the scan root, gitignore syntax) of files and directories to skip
+    pub exclude: Vec<String>,
+    /// Skip virtualenvs, caches, build output and vendored directories
+    pub default_excludes: bool,
+    /// Honor .gitignore and .ignore files
+    pub respect_gitignore: bool,
+    pub follow_links: bool,
+    /// Maximum directory depth below the scan root
+    pub max_depth: Option<usize>,
+    /// Also check conflicts across projects linked by path or editable requirements
+    pub cross_project: bool,
+    /// Python versions to evaluate `python_version` markers against
+    pub python_versions: Vec<String>,
+    /// Additional known-conflict rule files
+    pub rules: Vec<String>,
+    pub severity: SeverityConfig,
+    pub index: IndexConfig,
+    pub output: OutputConfig,
+    pub graph: GraphConfig,
+    /// Put packages no category matches into categories derived from the
+    /// trove classifiers of their package index metadata
+    pub trove_classifiers: bool,
+    /// Package categories used to cluster the graph
+    #[serde(rename = "category")]
+    pub categories: Vec<CategoryConfig>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct SeverityConfig {
+    /// Findings below this severity are not reported
+    pub min: Option<Severity>,
+    /// Exit with an error if a reported finding is at or above this severity
+    pub fail_on: Option<Severity>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct IndexConfig {
+    /// Base URL of the package index JSON API
+    pub url: String,
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        Self {
+            url: "https://pypi.org/pypi".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct OutputConfig {
+    pub format: Option<String>,
+    pub file: Option<String>,
+    pub baseline: Option<String>,
+    /// Levels shown below the roots of the text tree
+    pub tree_depth: Option<usize>,
+    /// Text tree lists dependents instead of dependencies
+    pub invert: bool,
+}
+
+/// Which part of the dependency graph to render. Applies to every graph
+/// format, so large graphs can be cut down to something readable.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct GraphConfig {
+    /// Only show what this package or project depends on, directly or not
+    pub focus: Option<String>,
+    /// Only show the packages and projects that lead to this package
+    pub reaching: Option<String>,
+    /// Maximum number of steps from `focus`/`reaching`, or from the projects
+    pub depth: Option<usize>,
+    /// Only show packages in these categories ("Other" and "Local Projects" included)
+    pub categories: Vec<String>,
+    /// Only follow requirements with these scopes; `optional` and `group`
+    /// match every extra and every dependency group
+    pub scopes: Vec<String>,
+    /// Hide packages without dependencies of their own
+    pub hide_leaves: bool,
+    /// Draw each category as a single node
+    pub collapse_clusters: bool,
+}
+
+impl GraphConfig {
+    pub fn is_active(&self) -> bool {
+        self.focus.is_some()
+            || self.reaching.is_some()
+            || self.depth.is_some()
+            || !self.categories.is_empty()
+            || !self.scopes.is_empty()
+            || self.hide_leaves
+            || self.collapse_clusters
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct CategoryConfig {
+    pub name: String,
+    /// Package names or globs such as `django-*`, compared after PEP 503
+    /// normalization
+    pub packages: Vec<String>,
+    /// Background of the category's cluster, as `#RRGGBB`
+    pub color: Option<String>,
+}
+
+impl CategoryConfig {
+    /// Compile the package patterns, checking the color on the way.
+    pub fn matcher(&self) -> Result<GlobSet> {
+        if let Some(color) = &self.color {
+            let valid = color.len() == 7
+                && color.starts_with('#')
+                && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+            if !valid {
+                anyhow::bail!("Invalid color '{}' for category '{}', expected #RRGGBB", color, self.name);
+            }
+        }
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &self.packages {
+            let glob = GlobBuilder::new(&normalize_name(pattern))
+                .case_insensitive(true)
+                .build()
+                .with_context(|| format!("Invalid package pattern '{}' in category '{}'", pattern, self.name))?;
+            builder.add(glob);
+        }
+        Ok(builder.build()?)
+    }
+}
+
+/// A rules file adding package pairs to the known-conflict table.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct RulesFile {
+    pub conflict: Vec<ConflictRule>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ConflictRule {
+    pub package: String,
+    pub conflicts_with: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let category = |name: &str, packages: &[&str]| CategoryConfig {
+            name: name.to_string(),
+            packages: packages.iter().map(|p| p.to_string()).collect(),
+            color: None,
+        };
+
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            default_excludes: true,
+            respect_gitignore: true,
+            follow_links: true,
+            max_depth: None,
+            cross_project: false,
+            python_versions: Vec::new(),
+            rules: Vec::new(),
+            severity: SeverityConfig::default(),
+            index: IndexConfig::default(),
+            output: OutputConfig::default(),
+            graph: GraphConfig::default(),
+            trove_classifiers: false,
+            categories: vec![
+                category(
+                    "Machine Learning",
+                    &["tensorflow", "torch", "jax", "transformers", "scikit-learn"],
+                ),
+                category("Data Processing", &["numpy", "pandas", "matplotlib", "seaborn"]),
+                category("Utilities", &["tqdm", "pillow", "tokenizers"]),
+            ],
+        }
+    }
+}
+
+impl Config {
+    /// Look for a configuration in `root` of `files`: `.dependency-analyzer.toml`
+    /// first, then the `[tool.dependency-analyzer]` table of `pyproject.toml`.
+    /// Returns the defaults and no path if neither exists.
+    pub fn discover(root: &Path, files: &FileSystem) -> Result<(Self, Option<PathBuf>)> {
+        let config_file = root.join(CONFIG_FILE_NAME);
+        if files.is_file(&config_file) {
+            return Ok((Self::load(&config_file, files)?, Some(config_file)));
+        }
+
+        let pyproject = root.join("pyproject.toml");
+        if files.is_file(&pyproject) {
+            let content = files
+                .read_to_string(&pyproject)
+                .with_context(|| format!("Failed to read {}", pyproject.display()))?;
+            // A broken pyproject.toml is reported by the scan itself
+            if let Ok(value) = toml::from_str::<toml::Value>(&content) {
+                if let Some(table) = value.get("tool").and_then(|t| t.get("dependency-analyzer")) {
+                    let mut config: Config = table.clone().try_into().with_context(|| {
+                        format!("Invalid [tool.dependency-analyzer] in {}", pyproject.display())
+                    })?;
+                    config.resolve_paths(root);
+                    return Ok((config, Some(pyproject)));
+                }
+            }
+        }
+
+        Ok((Self::default(), None))
+    }
+
+    pub fn load(path: &Path, files: &FileSystem) -> Result<Self> {
+        let content = files
+            .read_to_string(path)
+            .with_context(|| format!("Failed to read config {}", path.display()))?;
+        let mut config: Config = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config {}", path.display()))?;
+        config.resolve_paths(path.parent().unwrap_or_else(|| Path::new(".")));
+        Ok(config)
+    }
+
+    /// Make rules file paths relative to the directory the config lives in.
+    fn resolve_paths(&mut self, base: &Path) {
+        for rule in &mut self.rules {
+            if Path::new(rule).is_relative() {
+                *rule = base.join(&*rule).display().to_string();
+            }
+        }
+    }
+
+    /// Read the rules files, from the same place the config was read from.
+    pub fn load_rules(&self, files: &FileSystem) -> Result<Vec<ConflictRule>> {
+        let mut rules = Vec::new();
+        for file in &self.rules {
+            let content = files
+                .read_to_string(Path::new(file))
+                .with_context(|| format!("Failed to read rules file {}", file))?;
+            let rules_file: RulesFile = toml::from_str(&content)
+                .with_context(|| format!("Failed to parse rules file {}", file))?;
+            rules.extend(rules_file.conflict);
+        }
+        Ok(rules)
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string_pretty(self).context("Failed to serialize config")
+    }
+}
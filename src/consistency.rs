@@ -0,0 +1,159 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::specifier::VersionRange;
+use crate::table;
+use crate::tree::strip_name;
+use crate::DependencyAnalyzer;
+
+/// Version specs of one package across the projects of a repository.
+#[derive(Debug, Serialize)]
+pub struct ConsistencyRow {
+    pub package: String,
+    /// Specs declared per project id; projects not using the package are absent
+    pub specs: BTreeMap<String, Vec<String>>,
+    /// Intersection of every spec, `None` if they are disjoint or some
+    /// could not be parsed
+    pub suggested: Option<String>,
+    /// Specs whose version range could not be understood
+    pub unparsable: Vec<String>,
+    pub consistent: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConsistencyReport {
+    /// (id, name) of every project, in column order
+    pub projects: Vec<(String, String)>,
+    pub rows: Vec<ConsistencyRow>,
+}
+
+/// Build the package x project matrix for packages used by more than one
+/// project. Packages declared identically everywhere are only kept with
+/// `include_consistent`.
+pub fn build_report(analyzer: &DependencyAnalyzer, include_consistent: bool) -> ConsistencyReport {
+    let mut by_package: BTreeMap<&str, BTreeMap<String, Vec<String>>> = BTreeMap::new();
+    for dep in analyzer.dependencies.values().flatten() {
+        if dep.local_path.is_some() {
+            continue;
+        }
+        let spec = bare_spec(&dep.name, &dep.version_spec);
+        let specs = by_package
+            .entry(dep.name.as_str())
+            .or_default()
+            .entry(dep.project.clone())
+            .or_default();
+        if !specs.contains(&spec) {
+            specs.push(spec);
+        }
+    }
+
+    let mut rows = Vec::new();
+    for (package, mut specs) in by_package {
+        if specs.len() < 2 {
+            continue;
+        }
+        for project_specs in specs.values_mut() {
+            project_specs.sort();
+        }
+        let distinct: BTreeSet<&Vec<String>> = specs.values().collect();
+        let consistent = distinct.len() == 1;
+        if consistent && !include_consistent {
+            continue;
+        }
+
+        // Leaving out a spec would suggest a range it may not allow
+        let mut range = VersionRange::default();
+        let mut unparsable: Vec<String> = Vec::new();
+        for spec in specs.values().flatten() {
+            match VersionRange::parse(spec) {
+                Some(parsed) => range = range.intersect(&parsed),
+                None if !unparsable.contains(spec) => unparsable.push(spec.clone()),
+                None => {}
+            }
+        }
+        let suggested = if !unparsable.is_empty() || range.is_empty() {
+            None
+        } else {
+            Some(range.to_string())
+        };
+
+        rows.push(ConsistencyRow {
+            package: package.to_string(),
+            specs,
+            suggested,
+            unparsable,
+            consistent,
+        });
+    }
+
+    let used: BTreeSet<&String> = rows.iter().flat_map(|r| r.specs.keys()).collect();
+    let projects = analyzer
+        .projects
+        .iter()
+        .filter(|p| used.contains(&p.id))
+        .map(|p| (p.id.clone(), p.name.clone()))
+        .collect();
+
+    ConsistencyReport { projects, rows }
+}
+
+/// The version part of a requirement, `*` if there is none.
+fn bare_spec(name: &str, version_spec: &str) -> String {
+    let spec = strip_name(name, version_spec);
+    if spec.is_empty() {
+        "*".to_string()
+    } else {
+        spec.to_string()
+    }
+}
+
+impl ConsistencyReport {
+    fn table(&self) -> (Vec<String>, Vec<Vec<String>>) {
+        let mut header = vec!["Package".to_string()];
+        header.extend(self.projects.iter().map(|(_, name)| name.clone()));
+        header.push("Suggested".to_string());
+
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                let mut cells = vec![row.package.clone()];
+                for (id, _) in &self.projects {
+                    cells.push(
+                        row.specs
+                            .get(id)
+                            .map(|specs| specs.join(", "))
+                            .unwrap_or_else(|| "-".to_string()),
+                    );
+                }
+                cells.push(match &row.suggested {
+                    Some(suggested) => suggested.clone(),
+                    None if !row.unparsable.is_empty() => {
+                        format!("unparsable spec {}", row.unparsable.join(", "))
+                    }
+                    None => "no common range".to_string(),
+                });
+                cells
+            })
+            .collect();
+        (header, rows)
+    }
+
+    pub fn render_text(&self) -> String {
+        if self.rows.is_empty() {
+            return "No packages with differing version requirements across projects.\n"
+                .to_string();
+        }
+        let (header, rows) = self.table();
+        table::render_text(&header, &rows)
+    }
+
+    pub fn render_markdown(&self) -> String {
+        if self.rows.is_empty() {
+            return "No packages with differing version requirements across projects.\n"
+                .to_string();
+        }
+        let (header, rows) = self.table();
+        table::render_markdown(&header, &rows)
+    }
+}
@@ -0,0 +1,535 @@
+//! `diff` mode: what changed between two analyses, each a scanned tree, a
+//! saved JSON report or a git revision.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use crate::relative_path;
+use crate::report::{Report, SCHEMA_VERSION};
+use crate::table;
+use crate::tree::strip_name;
+
+/// The parts of a JSON report a diff looks at.
+#[derive(Debug, Default, Deserialize)]
+pub struct Snapshot {
+    schema_version: String,
+    projects: Vec<SnapshotProject>,
+    dependencies: Vec<SnapshotDependency>,
+    graph: SnapshotGraph,
+    findings: Vec<SnapshotFinding>,
+    diagnostics: Vec<SnapshotDiagnostic>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SnapshotProject {
+    name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SnapshotDependency {
+    name: String,
+    version_spec: String,
+    project: String,
+    scope: String,
+    location: SnapshotLocation,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SnapshotLocation {
+    file: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SnapshotDiagnostic {
+    location: SnapshotLocation,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SnapshotGraph {
+    nodes: Vec<SnapshotNode>,
+    edges: Vec<SnapshotEdge>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SnapshotNode {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SnapshotEdge {
+    from: String,
+    to: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotFinding {
+    pub rule: String,
+    pub severity: String,
+    pub message: String,
+    pub fingerprint: String,
+}
+
+impl Snapshot {
+    /// Snapshot of a fresh analysis. Messages name manifests by their full
+    /// path, so those paths are made relative to `root` to match across
+    /// checkouts.
+    pub fn from_report(report: &Report, root: &Path) -> Result<Self> {
+        let mut snapshot: Snapshot = serde_json::from_value(serde_json::to_value(report)?)?;
+        for finding in &mut snapshot.findings {
+            finding.message = relative_paths(&finding.message, root);
+        }
+        Ok(snapshot)
+    }
+
+    /// Snapshot of a report written by `--format json`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read report {}", path.display()))?;
+        let snapshot: Snapshot = serde_json::from_str(&content)
+            .with_context(|| format!("{} is not a dependency-analyzer JSON report", path.display()))?;
+        let major = |version: &str| version.split('.').next().unwrap_or_default().to_string();
+        if major(&snapshot.schema_version) != major(SCHEMA_VERSION) {
+            anyhow::bail!(
+                "Report {} has schema version {}, expected {}.x",
+                path.display(),
+                snapshot.schema_version,
+                major(SCHEMA_VERSION)
+            );
+        }
+        Ok(snapshot)
+    }
+
+    /// Packages only reachable through other packages: neither required by
+    /// a project directly nor a project themselves.
+    fn transitive(&self) -> BTreeSet<&str> {
+        let direct: BTreeSet<&str> = self.dependencies.iter().map(|d| d.name.as_str()).collect();
+        let projects: BTreeSet<&str> = self.projects.iter().map(|p| p.name.as_str()).collect();
+        let mut edges: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for edge in &self.graph.edges {
+            edges.entry(edge.from.as_str()).or_default().push(edge.to.as_str());
+        }
+        let mut reached: BTreeSet<&str> = BTreeSet::new();
+        let mut stack: Vec<&str> = direct.iter().copied().collect();
+        while let Some(node) = stack.pop() {
+            for &next in edges.get(node).into_iter().flatten() {
+                if reached.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+        reached
+            .into_iter()
+            .filter(|id| !direct.contains(id) && !projects.contains(id))
+            .collect()
+    }
+
+    /// Requirement specs by (project, package, scope), `*` for none.
+    pub fn scoped_requirements(&self) -> BTreeMap<(String, String, String), BTreeSet<String>> {
+        let mut requirements: BTreeMap<_, BTreeSet<String>> = BTreeMap::new();
+        for dep in &self.dependencies {
+            let spec = strip_name(&dep.name, &dep.version_spec);
+            let spec = if spec.is_empty() { "*" } else { spec };
+            requirements
+                .entry((dep.project.clone(), dep.name.clone(), dep.scope.clone()))
+                .or_default()
+                .insert(spec.to_string());
+        }
+        requirements
+    }
+
+    /// Requirement specs by (project, package), as `spec <scope>`.
+    fn requirements(&self) -> BTreeMap<(String, String), BTreeSet<String>> {
+        let mut requirements: BTreeMap<(String, String), BTreeSet<String>> = BTreeMap::new();
+        for ((project, package, scope), specs) in self.scoped_requirements() {
+            let entry = requirements.entry((project, package)).or_default();
+            for mut spec in specs {
+                if scope != "main" {
+                    let _ = write!(spec, " <{}>", scope);
+                }
+                entry.insert(spec);
+            }
+        }
+        requirements
+    }
+
+    /// Keep the requirements `previous` had in manifests that could not be
+    /// parsed this time, rather than reporting them as removed.
+    pub fn carry_forward_unparsed(&mut self, previous: &Snapshot) {
+        let parsed: BTreeSet<&str> =
+            self.dependencies.iter().map(|d| d.location.file.as_str()).collect();
+        let failed: BTreeSet<String> = self
+            .diagnostics
+            .iter()
+            .map(|d| d.location.file.clone())
+            .filter(|file| !parsed.contains(file.as_str()))
+            .collect();
+        self.dependencies.extend(
+            previous
+                .dependencies
+                .iter()
+                .filter(|d| failed.contains(&d.location.file))
+                .cloned(),
+        );
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffReport {
+    pub old: String,
+    pub new: String,
+    pub added: Vec<DependencyChange>,
+    pub removed: Vec<DependencyChange>,
+    /// Requirements whose version specs or scopes changed
+    pub changed: Vec<DependencyChange>,
+    /// Packages new to the graph that no project requires directly
+    pub new_transitive: Vec<String>,
+    pub new_findings: Vec<SnapshotFinding>,
+    pub resolved_findings: Vec<SnapshotFinding>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DependencyChange {
+    /// Project id
+    pub project: String,
+    pub package: String,
+    /// Specs before, as `spec <scope>` for scopes other than main
+    pub old: Vec<String>,
+    pub new: Vec<String>,
+}
+
+/// Compare the `old` and `new` snapshots, labelled for the report.
+pub fn compare(old_label: &str, old: &Snapshot, new_label: &str, new: &Snapshot) -> DiffReport {
+    let before = old.requirements();
+    let after = new.requirements();
+    let change = |key: &(String, String)| DependencyChange {
+        project: key.0.clone(),
+        package: key.1.clone(),
+        old: before.get(key).into_iter().flatten().cloned().collect(),
+        new: after.get(key).into_iter().flatten().cloned().collect(),
+    };
+    let added = after.keys().filter(|k| !before.contains_key(*k)).map(change).collect();
+    let removed = before.keys().filter(|k| !after.contains_key(*k)).map(change).collect();
+    let changed = after
+        .iter()
+        .filter(|(k, specs)| before.get(*k).is_some_and(|old| old != *specs))
+        .map(|(k, _)| change(k))
+        .collect();
+
+    let old_nodes: BTreeSet<&str> = old.graph.nodes.iter().map(|n| n.id.as_str()).collect();
+    let new_transitive = new
+        .transitive()
+        .into_iter()
+        .filter(|id| !old_nodes.contains(id))
+        .map(str::to_string)
+        .collect();
+
+    let fingerprints = |snapshot: &Snapshot| -> BTreeSet<String> {
+        snapshot.findings.iter().map(|f| f.fingerprint.clone()).collect()
+    };
+    let (old_prints, new_prints) = (fingerprints(old), fingerprints(new));
+    let new_findings = new
+        .findings
+        .iter()
+        .filter(|f| !old_prints.contains(&f.fingerprint))
+        .cloned()
+        .collect();
+    let resolved_findings = old
+        .findings
+        .iter()
+        .filter(|f| !new_prints.contains(&f.fingerprint))
+        .cloned()
+        .collect();
+
+    DiffReport {
+        old: old_label.to_string(),
+        new: new_label.to_string(),
+        added,
+        removed,
+        changed,
+        new_transitive,
+        new_findings,
+        resolved_findings,
+    }
+}
+
+impl DiffReport {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.new_transitive.is_empty()
+            && self.new_findings.is_empty()
+            && self.resolved_findings.is_empty()
+    }
+
+    pub fn render_text(&self) -> String {
+        let mut output = format!("Comparing {} -> {}\n", self.old, self.new);
+        if self.is_empty() {
+            output.push_str("\nNo differences.\n");
+            return output;
+        }
+        let sections = [
+            ("Added dependencies", "+", &self.added),
+            ("Removed dependencies", "-", &self.removed),
+            ("Changed version ranges", "~", &self.changed),
+        ];
+        for (title, marker, changes) in sections {
+            if changes.is_empty() {
+                continue;
+            }
+            let _ = writeln!(output, "\n{}:", title);
+            for change in changes {
+                let _ = writeln!(
+                    output,
+                    "  {} {}: {} {}",
+                    marker,
+                    change.project,
+                    change.package,
+                    describe_specs(change)
+                );
+            }
+        }
+        if !self.new_transitive.is_empty() {
+            let _ = writeln!(output, "\nNew transitive packages:");
+            for package in &self.new_transitive {
+                let _ = writeln!(output, "  + {}", package);
+            }
+        }
+        for (title, marker, findings) in [
+            ("New findings", "+", &self.new_findings),
+            ("Resolved findings", "-", &self.resolved_findings),
+        ] {
+            if findings.is_empty() {
+                continue;
+            }
+            let _ = writeln!(output, "\n{}:", title);
+            for finding in findings {
+                let _ = writeln!(
+                    output,
+                    "  {} [{}] {}",
+                    marker,
+                    finding.rule,
+                    finding.message.lines().next().unwrap_or_default()
+                );
+            }
+        }
+        output
+    }
+
+    /// Markdown for a pull request comment.
+    pub fn render_markdown(&self) -> String {
+        let mut output = format!("### Dependency changes: `{}` → `{}`\n", self.old, self.new);
+        if self.is_empty() {
+            output.push_str("\nNo differences.\n");
+            return output;
+        }
+        let header: Vec<String> = ["Project", "Package", "Before", "After"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        let sections = [
+            ("Added dependencies", &self.added),
+            ("Removed dependencies", &self.removed),
+            ("Changed version ranges", &self.changed),
+        ];
+        for (title, changes) in sections {
+            if changes.is_empty() {
+                continue;
+            }
+            let rows: Vec<Vec<String>> = changes
+                .iter()
+                .map(|c| {
+                    let specs = |specs: &[String]| {
+                        if specs.is_empty() {
+                            "-".to_string()
+                        } else {
+                            format!("`{}`", specs.join("`, `"))
+                        }
+                    };
+                    vec![c.project.clone(), c.package.clone(), specs(&c.old), specs(&c.new)]
+                })
+                .collect();
+            let _ = write!(output, "\n**{}**\n\n{}", title, table::render_markdown(&header, &rows));
+        }
+        if !self.new_transitive.is_empty() {
+            let _ = writeln!(output, "\n**New transitive packages**\n");
+            for package in &self.new_transitive {
+                let _ = writeln!(output, "- {}", package);
+            }
+        }
+        for (title, findings) in [
+            ("New findings", &self.new_findings),
+            ("Resolved findings", &self.resolved_findings),
+        ] {
+            if findings.is_empty() {
+                continue;
+            }
+            let _ = writeln!(output, "\n**{}**\n", title);
+            for finding in findings {
+                let _ = writeln!(
+                    output,
+                    "- **{}** `{}`: {}",
+                    finding.severity,
+                    finding.rule,
+                    finding.message.lines().next().unwrap_or_default()
+                );
+            }
+        }
+        output
+    }
+}
+
+/// `message` with every path below `root` made relative to it, leaving
+/// other paths such as `../lib` alone.
+fn relative_paths(message: &str, root: &Path) -> String {
+    let mut output = String::with_capacity(message.len());
+    for word in message.split_inclusive(char::is_whitespace) {
+        // A path sits between an optional opening parenthesis and a
+        // `:line:column`, `)` or `,` suffix
+        let start = word.len() - word.trim_start_matches('(').len();
+        let end = word[start..]
+            .find([':', ')', ','])
+            .map_or(word.trim_end().len(), |i| start + i);
+        let path = Path::new(&word[start..end]);
+        if end > start && path.starts_with(root) {
+            output.push_str(&word[..start]);
+            output.push_str(&relative_path(&word[start..end], root));
+            output.push_str(&word[end..]);
+        } else {
+            output.push_str(word);
+        }
+    }
+    output
+}
+
+/// `old -> new` for changes, the specs alone for additions and removals.
+fn describe_specs(change: &DependencyChange) -> String {
+    match (change.old.is_empty(), change.new.is_empty()) {
+        (true, _) => change.new.join(", "),
+        (_, true) => change.old.join(", "),
+        _ => format!("{} -> {}", change.old.join(", "), change.new.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A snapshot with `projects`, `(project, requirement, scope)` entries,
+    /// graph edges and `(rule, fingerprint)` findings.
+    fn snapshot(
+        projects: &[&str],
+        dependencies: &[(&str, &str, &str)],
+        edges: &[(&str, &str)],
+        findings: &[(&str, &str)],
+    ) -> Snapshot {
+        let mut nodes: BTreeSet<&str> = projects.iter().copied().collect();
+        nodes.extend(edges.iter().flat_map(|(from, to)| [*from, *to]));
+        let dependencies: Vec<_> = dependencies
+            .iter()
+            .map(|(project, requirement, scope)| {
+                let name = requirement.split(['=', '>', '<', '~']).next().unwrap();
+                nodes.insert(name);
+                json!({
+                    "name": name,
+                    "version_spec": requirement,
+                    "project": project,
+                    "scope": scope,
+                    "location": { "file": format!("{}/pyproject.toml", project) },
+                })
+            })
+            .collect();
+        serde_json::from_value(json!({
+            "schema_version": SCHEMA_VERSION,
+            "projects": projects.iter().map(|p| json!({ "name": p })).collect::<Vec<_>>(),
+            "dependencies": dependencies,
+            "graph": {
+                "nodes": nodes.iter().map(|n| json!({ "id": n })).collect::<Vec<_>>(),
+                "edges": edges
+                    .iter()
+                    .map(|(from, to)| json!({ "from": from, "to": to }))
+                    .collect::<Vec<_>>(),
+            },
+            "findings": findings
+                .iter()
+                .map(|(rule, fingerprint)| json!({
+                    "rule": rule,
+                    "severity": "warning",
+                    "message": format!("{} finding", rule),
+                    "fingerprint": fingerprint,
+                }))
+                .collect::<Vec<_>>(),
+            "diagnostics": [],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn requirement_changes_are_split_by_kind() {
+        let old = snapshot(
+            &["app"],
+            &[("app", "flask>=2", "main"), ("app", "numpy", "main")],
+            &[],
+            &[],
+        );
+        let new = snapshot(
+            &["app"],
+            &[("app", "flask>=3", "main"), ("app", "pytest", "dev")],
+            &[],
+            &[],
+        );
+        let report = compare("old", &old, "new", &new);
+        assert_eq!(report.added.len(), 1);
+        assert_eq!(report.added[0].package, "pytest");
+        assert_eq!(report.added[0].new, vec!["* <dev>"]);
+        assert_eq!(report.removed[0].package, "numpy");
+        assert_eq!(report.changed[0].old, vec![">=2"]);
+        assert_eq!(report.changed[0].new, vec![">=3"]);
+    }
+
+    #[test]
+    fn added_project_is_not_a_transitive_package() {
+        let old = snapshot(&["svc-a"], &[("svc-a", "requests", "main")], &[], &[]);
+        let new = snapshot(
+            &["svc-a", "svc-c"],
+            &[("svc-a", "requests", "main"), ("svc-c", "flask", "main")],
+            &[("svc-c", "flask"), ("flask", "jinja2"), ("jinja2", "markupsafe")],
+            &[],
+        );
+        let report = compare("old", &old, "new", &new);
+        assert_eq!(report.new_transitive, vec!["jinja2", "markupsafe"]);
+        assert_eq!(report.added.len(), 1);
+    }
+
+    #[test]
+    fn findings_are_matched_by_fingerprint() {
+        let old = snapshot(&["app"], &[], &[], &[("multiple-versions", "aaa"), ("cycle", "bbb")]);
+        let new = snapshot(&["app"], &[], &[], &[("multiple-versions", "aaa"), ("cycle", "ccc")]);
+        let report = compare("old", &old, "new", &new);
+        let prints = |findings: &[SnapshotFinding]| {
+            findings.iter().map(|f| f.fingerprint.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(prints(&report.new_findings), vec!["ccc"]);
+        assert_eq!(prints(&report.resolved_findings), vec!["bbb"]);
+        assert!(compare("old", &old, "old", &old).is_empty());
+    }
+
+    #[test]
+    fn relative_paths_only_shortens_paths_below_the_root() {
+        let message = "numpy<2 (in ./pyproject.toml:3:16), numpy>=2 (in ../lib/setup.py)";
+        assert_eq!(
+            relative_paths(message, Path::new(".")),
+            "numpy<2 (in pyproject.toml:3:16), numpy>=2 (in ../lib/setup.py)"
+        );
+        let message = "cycle via /repo/svc/pyproject.toml and /repository/x";
+        assert_eq!(
+            relative_paths(message, Path::new("/repo")),
+            "cycle via svc/pyproject.toml and /repository/x"
+        );
+    }
+}
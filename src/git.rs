@@ -0,0 +1,92 @@
+//! Access to git revisions through the `git` command line.
+
+use anyhow::{Context, Result};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+/// Run git in `repo` and return its trimmed standard output.
+pub fn run(repo: &Path, args: &[&str]) -> Result<String> {
+    let stdout = output(repo, args)?;
+    Ok(String::from_utf8_lossy(&stdout).trim_end().to_string())
+}
+
+fn output(repo: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+/// Full commit id of `revision` in `repo`.
+pub fn resolve(repo: &Path, revision: &str) -> Result<String> {
+    run(repo, &["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", revision)])
+        .with_context(|| format!("Unknown git revision '{}'", revision))
+}
+
+/// A `git cat-file --batch` process reading any number of blobs, so a scan
+/// does not start one git process per file.
+pub struct BlobReader {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl BlobReader {
+    pub fn open(repo: &Path) -> Result<Self> {
+        let mut child = Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(["cat-file", "--batch"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("Failed to run git")?;
+        let stdin = child.stdin.take().context("git cat-file has no stdin")?;
+        let stdout = child.stdout.take().context("git cat-file has no stdout")?;
+        Ok(Self {
+            child,
+            stdin: Some(stdin),
+            stdout: BufReader::new(stdout),
+        })
+    }
+
+    /// Content of the blob `id`, which must be UTF-8 text.
+    pub fn read(&mut self, id: &str) -> Result<String> {
+        let stdin = self.stdin.as_mut().context("git cat-file was closed")?;
+        writeln!(stdin, "{}", id)?;
+        stdin.flush()?;
+
+        // `<id> <type> <size>`, or `<id> missing`
+        let mut header = String::new();
+        self.stdout.read_line(&mut header)?;
+        let size = match header.split_whitespace().collect::<Vec<_>>()[..] {
+            [_, "blob", size] => size.parse::<usize>()?,
+            _ => anyhow::bail!("git cat-file cannot read blob {}: {}", id, header.trim()),
+        };
+        // The content is followed by a newline
+        let mut content = vec![0; size + 1];
+        self.stdout.read_exact(&mut content)?;
+        content.truncate(size);
+        String::from_utf8(content).with_context(|| format!("Blob {} is not valid UTF-8", id))
+    }
+}
+
+impl Drop for BlobReader {
+    fn drop(&mut self) {
+        // Closing stdin ends the batch
+        self.stdin.take();
+        let _ = self.child.wait();
+    }
+}
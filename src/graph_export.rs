@@ -0,0 +1,334 @@
+//! Graph exports for other tools: Mermaid flowcharts for Markdown docs,
+//! GraphML for yEd, GEXF for Gephi and Cytoscape.js JSON. Every format
+//! carries the category, conflict status and version specs of each node, so
+//! the DOT styling can be rebuilt on the other side.
+
+use anyhow::Result;
+use serde_json::json;
+use std::fmt::Write;
+
+use crate::svg::escape;
+use crate::DependencyAnalyzer;
+
+pub const FORMATS: &[&str] = &["mermaid", "graphml", "gexf", "cytoscape"];
+
+/// A graph node with the attributes shared by all exports.
+struct Node {
+    /// Index in the analyzer graph, used for ids that must be identifiers
+    index: usize,
+    name: String,
+    /// Cluster id and label, as in the DOT output
+    cluster: String,
+    category: String,
+    status: &'static str,
+    color: &'static str,
+    local: bool,
+    /// Distinct version specs the package is required with, sorted
+    version_specs: Vec<String>,
+}
+
+struct Edge {
+    /// Graph indices of the endpoints, for Mermaid ids
+    from: usize,
+    to: usize,
+    source: String,
+    target: String,
+    conflict: bool,
+}
+
+struct ExportGraph {
+    /// (cluster id, label, configured color) of every non-empty cluster,
+    /// in graph order
+    clusters: Vec<(String, String, Option<String>)>,
+    /// Sorted by name
+    nodes: Vec<Node>,
+    /// Sorted by (from, to) name
+    edges: Vec<Edge>,
+}
+
+impl ExportGraph {
+    fn build(analyzer: &DependencyAnalyzer) -> Self {
+        let graph = &analyzer.graph;
+        let mut clusters = Vec::new();
+        let mut nodes = Vec::new();
+        for (cluster, label, members) in analyzer.graph_clusters() {
+            if members.is_empty() {
+                continue;
+            }
+            for node in members {
+                let name = &graph[node];
+                let mut version_specs: Vec<String> = analyzer
+                    .dependencies
+                    .get(name)
+                    .into_iter()
+                    .flatten()
+                    .filter(|d| d.local_path.is_none())
+                    .map(|d| d.version_spec.clone())
+                    .filter(|spec| !spec.is_empty())
+                    .collect();
+                version_specs.sort();
+                version_specs.dedup();
+                nodes.push(Node {
+                    index: node.index(),
+                    name: name.clone(),
+                    cluster: cluster.clone(),
+                    category: label.clone(),
+                    status: analyzer.conflict_status(name),
+                    color: analyzer.node_style(name).0,
+                    local: analyzer.local_packages.contains(name),
+                    version_specs,
+                });
+            }
+            let color = analyzer.category_color(&label).map(str::to_string);
+            clusters.push((cluster, label, color));
+        }
+        nodes.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let edges = analyzer
+            .sorted_edges()
+            .into_iter()
+            .map(|(from, to)| Edge {
+                from: from.index(),
+                to: to.index(),
+                source: graph[from].clone(),
+                target: graph[to].clone(),
+                conflict: analyzer.is_conflict_edge(from, to),
+            })
+            .collect();
+
+        Self {
+            clusters,
+            nodes,
+            edges,
+        }
+    }
+}
+
+/// Render the graph of `analyzer` in one of `FORMATS`.
+pub fn render(analyzer: &DependencyAnalyzer, format: &str) -> Result<String> {
+    let graph = ExportGraph::build(analyzer);
+    match format {
+        "mermaid" => Ok(mermaid(&graph)),
+        "graphml" => Ok(graphml(&graph)),
+        "gexf" => Ok(gexf(&graph)),
+        "cytoscape" => Ok(serde_json::to_string_pretty(&cytoscape(&graph))? + "\n"),
+        _ => Err(anyhow::anyhow!("Unsupported graph export format '{}'", format)),
+    }
+}
+
+/// Mermaid flowchart; conflict status becomes a class and the version
+/// specs a second label line.
+fn mermaid(graph: &ExportGraph) -> String {
+    // Mermaid labels use HTML entities, with `#quot;` for quotes
+    let label = |text: &str| {
+        text.replace('&', "#amp;")
+            .replace('"', "#quot;")
+            .replace('<', "#lt;")
+            .replace('>', "#gt;")
+    };
+
+    let mut out = String::from("flowchart LR\n");
+    out.push_str("    classDef multipleVersions stroke:#CC0000,color:#CC0000\n");
+    out.push_str("    classDef knownConflict stroke:#FF6600,color:#FF6600\n");
+    for (cluster, cluster_label, _) in &graph.clusters {
+        let _ = writeln!(out, "    subgraph {} [\"{}\"]", cluster, label(cluster_label));
+        for node in graph.nodes.iter().filter(|n| &n.cluster == cluster) {
+            let mut text = label(&node.name);
+            if !node.version_specs.is_empty() {
+                let _ = write!(text, "<br/><small>{}</small>", label(&node.version_specs.join(" | ")));
+            }
+            let _ = writeln!(out, "        n{}[\"{}\"]", node.index, text);
+        }
+        out.push_str("    end\n");
+    }
+    for (cluster, _, color) in &graph.clusters {
+        if let Some(color) = color {
+            let _ = writeln!(out, "    style {} fill:{}", cluster, color);
+        }
+    }
+    let mut conflict_links = Vec::new();
+    for (i, edge) in graph.edges.iter().enumerate() {
+        let arrow = if edge.conflict { "-.->" } else { "-->" };
+        let _ = writeln!(out, "    n{} {} n{}", edge.from, arrow, edge.to);
+        if edge.conflict {
+            conflict_links.push(i.to_string());
+        }
+    }
+    if !conflict_links.is_empty() {
+        let _ = writeln!(
+            out,
+            "    linkStyle {} stroke:#CC0000,stroke-width:2px",
+            conflict_links.join(",")
+        );
+    }
+    for (status, class) in [("multiple-versions", "multipleVersions"), ("known-conflict", "knownConflict")] {
+        let members: Vec<String> = graph
+            .nodes
+            .iter()
+            .filter(|n| n.status == status)
+            .map(|n| format!("n{}", n.index))
+            .collect();
+        if !members.is_empty() {
+            let _ = writeln!(out, "    class {} {}", members.join(","), class);
+        }
+    }
+    out
+}
+
+/// Node attributes of the XML formats as (key, type).
+const NODE_ATTRIBUTES: [(&str, &str); 5] = [
+    ("category", "string"),
+    ("status", "string"),
+    ("version_spec", "string"),
+    ("local", "boolean"),
+    ("color", "string"),
+];
+
+/// Values of `NODE_ATTRIBUTES` for `node`.
+fn node_attributes(node: &Node) -> [String; 5] {
+    [
+        node.category.clone(),
+        node.status.to_string(),
+        node.version_specs.join(" | "),
+        node.local.to_string(),
+        node.color.to_string(),
+    ]
+}
+
+/// GraphML with `data` keys for every node attribute.
+fn graphml(graph: &ExportGraph) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">\n");
+    out.push_str("  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n");
+    for (key, kind) in NODE_ATTRIBUTES {
+        let _ = writeln!(
+            out,
+            "  <key id=\"{key}\" for=\"node\" attr.name=\"{key}\" attr.type=\"{kind}\"/>"
+        );
+    }
+    out.push_str("  <key id=\"conflict\" for=\"edge\" attr.name=\"conflict\" attr.type=\"boolean\"/>\n");
+    out.push_str("  <graph id=\"dependencies\" edgedefault=\"directed\">\n");
+    for node in &graph.nodes {
+        let _ = writeln!(out, "    <node id=\"{}\">", escape(&node.name));
+        let _ = writeln!(out, "      <data key=\"label\">{}</data>", escape(&node.name));
+        for ((key, _), value) in NODE_ATTRIBUTES.iter().zip(node_attributes(node)) {
+            let _ = writeln!(out, "      <data key=\"{}\">{}</data>", key, escape(&value));
+        }
+        out.push_str("    </node>\n");
+    }
+    for (i, edge) in graph.edges.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "    <edge id=\"e{}\" source=\"{}\" target=\"{}\">\n      <data key=\"conflict\">{}</data>\n    </edge>",
+            i,
+            escape(&edge.source),
+            escape(&edge.target),
+            edge.conflict
+        );
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+/// GEXF 1.3 with node attributes and `viz:color` from the conflict status.
+fn gexf(graph: &ExportGraph) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<gexf xmlns=\"http://gexf.net/1.3\" xmlns:viz=\"http://gexf.net/1.3/viz\" version=\"1.3\">\n");
+    let _ = writeln!(
+        out,
+        "  <meta>\n    <creator>{} {}</creator>\n  </meta>",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    );
+    out.push_str("  <graph defaultedgetype=\"directed\" mode=\"static\">\n");
+    out.push_str("    <attributes class=\"node\">\n");
+    for (i, (key, kind)) in NODE_ATTRIBUTES.iter().enumerate() {
+        let _ = writeln!(out, "      <attribute id=\"{}\" title=\"{}\" type=\"{}\"/>", i, key, kind);
+    }
+    out.push_str("    </attributes>\n");
+    out.push_str("    <attributes class=\"edge\">\n");
+    out.push_str("      <attribute id=\"0\" title=\"conflict\" type=\"boolean\"/>\n");
+    out.push_str("    </attributes>\n");
+
+    out.push_str("    <nodes>\n");
+    for node in &graph.nodes {
+        let _ = writeln!(
+            out,
+            "      <node id=\"{}\" label=\"{}\">\n        <attvalues>",
+            escape(&node.name),
+            escape(&node.name)
+        );
+        for (i, value) in node_attributes(node).iter().enumerate() {
+            let _ = writeln!(out, "          <attvalue for=\"{}\" value=\"{}\"/>", i, escape(value));
+        }
+        let (r, g, b) = rgb(node.color);
+        let _ = writeln!(
+            out,
+            "        </attvalues>\n        <viz:color r=\"{}\" g=\"{}\" b=\"{}\"/>\n      </node>",
+            r, g, b
+        );
+    }
+    out.push_str("    </nodes>\n    <edges>\n");
+    for (i, edge) in graph.edges.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "      <edge id=\"{}\" source=\"{}\" target=\"{}\">\n        <attvalues>\n          <attvalue for=\"0\" value=\"{}\"/>\n        </attvalues>\n      </edge>",
+            i,
+            escape(&edge.source),
+            escape(&edge.target),
+            edge.conflict
+        );
+    }
+    out.push_str("    </edges>\n  </graph>\n</gexf>\n");
+    out
+}
+
+/// Cytoscape.js elements JSON; clusters become compound parent nodes.
+fn cytoscape(graph: &ExportGraph) -> serde_json::Value {
+    let mut nodes: Vec<serde_json::Value> = graph
+        .clusters
+        .iter()
+        .map(|(id, label, color)| json!({ "data": { "id": id, "label": label, "color": color } }))
+        .collect();
+    nodes.extend(graph.nodes.iter().map(|node| {
+        json!({
+            "data": {
+                "id": node.name,
+                "label": node.name,
+                "parent": node.cluster,
+                "category": node.category,
+                "status": node.status,
+                "version_specs": node.version_specs,
+                "local": node.local,
+                "color": node.color,
+            }
+        })
+    }));
+    let edges: Vec<serde_json::Value> = graph
+        .edges
+        .iter()
+        .enumerate()
+        .map(|(i, edge)| {
+            json!({
+                "data": {
+                    "id": format!("e{}", i),
+                    "source": edge.source,
+                    "target": edge.target,
+                    "conflict": edge.conflict,
+                }
+            })
+        })
+        .collect();
+    json!({ "elements": { "nodes": nodes, "edges": edges } })
+}
+
+/// `#RRGGBB` to components.
+fn rgb(color: &str) -> (u8, u8, u8) {
+    let channel = |i: usize| {
+        color
+            .get(i..i + 2)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            .unwrap_or(0)
+    };
+    (channel(1), channel(3), channel(5))
+}
@@ -0,0 +1,351 @@
+//! `history` mode: when each requirement was added, changed and removed,
+//! from the manifests at every commit that touched them.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::diff::Snapshot;
+use crate::git;
+use crate::lockfile::normalize_name;
+use crate::walk::MANIFEST_NAMES;
+
+/// A commit of the walked history.
+#[derive(Debug, Clone)]
+pub struct Commit {
+    pub id: String,
+    pub author: String,
+    /// Author date, ISO 8601
+    pub date: String,
+    pub summary: String,
+}
+
+/// Commits that changed a manifest below `root`, oldest first. Only the
+/// first parent of merges is followed, so changes made on a branch show up
+/// with the merge that brought them in.
+pub fn commits(root: &Path) -> Result<Vec<Commit>> {
+    let pathspecs: Vec<String> = MANIFEST_NAMES
+        .iter()
+        .map(|name| format!(":(glob)**/{}", name))
+        .collect();
+    let mut args = vec![
+        "log",
+        "--first-parent",
+        "--reverse",
+        "--format=%H%x1f%an%x1f%aI%x1f%s",
+        "--",
+    ];
+    args.extend(pathspecs.iter().map(String::as_str));
+    let log = git::run(root, &args)?;
+    Ok(log
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\x1f');
+            Some(Commit {
+                id: fields.next()?.to_string(),
+                author: fields.next()?.to_string(),
+                date: fields.next()?.to_string(),
+                summary: fields.next().unwrap_or_default().to_string(),
+            })
+        })
+        .collect())
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistoryReport {
+    /// Commits walked
+    pub commits: usize,
+    pub packages: Vec<PackageTimeline>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackageTimeline {
+    pub package: String,
+    /// Oldest first
+    pub events: Vec<Event>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Event {
+    /// added, changed or removed
+    pub change: &'static str,
+    /// Project id
+    pub project: String,
+    /// main, dev, optional:<extra> or group:<name>
+    pub scope: String,
+    /// Specs before and after, `*` for none
+    pub old: Vec<String>,
+    pub new: Vec<String>,
+    pub commit: String,
+    pub author: String,
+    pub date: String,
+    pub summary: String,
+}
+
+/// Builds the timelines from the snapshots of consecutive commits.
+pub struct Timeline {
+    previous: Snapshot,
+    commits: usize,
+    /// Only these packages (normalized), or all when empty
+    packages: Vec<String>,
+    events: BTreeMap<String, Vec<Event>>,
+}
+
+impl Timeline {
+    pub fn new(packages: &[String]) -> Self {
+        Self {
+            previous: Snapshot::default(),
+            commits: 0,
+            packages: packages.iter().map(|p| normalize_name(p)).collect(),
+            events: BTreeMap::new(),
+        }
+    }
+
+    /// Record what changed between the previous commit and `commit`. A
+    /// manifest that fails to parse keeps its previous requirements, so a
+    /// broken commit does not show up as everything removed and re-added.
+    pub fn record(&mut self, commit: &Commit, mut snapshot: Snapshot) {
+        snapshot.carry_forward_unparsed(&self.previous);
+        let before = self.previous.scoped_requirements();
+        let after = snapshot.scoped_requirements();
+        let keys: BTreeSet<&(String, String, String)> = before.keys().chain(after.keys()).collect();
+        for key in keys {
+            let (old, new) = (before.get(key), after.get(key));
+            let change = match (old, new) {
+                (None, Some(_)) => "added",
+                (Some(_), None) => "removed",
+                (Some(old), Some(new)) if old != new => "changed",
+                _ => continue,
+            };
+            let (project, package, scope) = key;
+            if !self.packages.is_empty() && !self.packages.contains(&normalize_name(package)) {
+                continue;
+            }
+            self.events.entry(package.clone()).or_default().push(Event {
+                change,
+                project: project.clone(),
+                scope: scope.clone(),
+                old: old.into_iter().flatten().cloned().collect(),
+                new: new.into_iter().flatten().cloned().collect(),
+                commit: commit.id.clone(),
+                author: commit.author.clone(),
+                date: commit.date.clone(),
+                summary: commit.summary.clone(),
+            });
+        }
+        self.previous = snapshot;
+        self.commits += 1;
+    }
+
+    pub fn finish(self) -> HistoryReport {
+        HistoryReport {
+            commits: self.commits,
+            packages: self
+                .events
+                .into_iter()
+                .map(|(package, events)| PackageTimeline { package, events })
+                .collect(),
+        }
+    }
+}
+
+impl HistoryReport {
+    pub fn render_text(&self) -> String {
+        let mut output = format!("Walked {} commit(s) that changed manifests\n", self.commits);
+        if self.packages.is_empty() {
+            output.push_str("\nNo requirement changes.\n");
+            return output;
+        }
+        for timeline in &self.packages {
+            let _ = writeln!(output, "\n{}:", timeline.package);
+            for event in &timeline.events {
+                let (marker, mut specs) = match event.change {
+                    "added" => ("+", event.new.join(", ")),
+                    "removed" => ("-", event.old.join(", ")),
+                    _ => ("~", format!("{} -> {}", event.old.join(", "), event.new.join(", "))),
+                };
+                if event.scope != "main" {
+                    let _ = write!(specs, " <{}>", event.scope);
+                }
+                let _ = writeln!(
+                    output,
+                    "  {} {} {} {}: {} ({}, \"{}\")",
+                    &event.date[..event.date.len().min(10)],
+                    &event.commit[..event.commit.len().min(7)],
+                    marker,
+                    event.project,
+                    specs,
+                    event.author,
+                    event.summary
+                );
+            }
+        }
+        output
+    }
+
+    /// One row per event, oldest first within each package.
+    pub fn render_csv(&self) -> String {
+        let mut output =
+            String::from("package,project,scope,change,old,new,commit,author,date,summary\n");
+        for timeline in &self.packages {
+            for event in &timeline.events {
+                let row = [
+                    timeline.package.as_str(),
+                    &event.project,
+                    &event.scope,
+                    event.change,
+                    &event.old.join(", "),
+                    &event.new.join(", "),
+                    &event.commit,
+                    &event.author,
+                    &event.date,
+                    &event.summary,
+                ];
+                let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+                let _ = writeln!(output, "{}", row.join(","));
+            }
+        }
+        output
+    }
+}
+
+/// Quote fields containing separators, quotes or line breaks (RFC 4180).
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn commit(id: &str) -> Commit {
+        Commit {
+            id: id.repeat(8),
+            author: "Ann".to_string(),
+            date: "2026-01-02T03:04:05+00:00".to_string(),
+            summary: format!("commit {}", id),
+        }
+    }
+
+    /// A snapshot of `app/pyproject.toml` with `(requirement, scope)` entries,
+    /// or a parse error for it when `broken`.
+    fn snapshot(requirements: &[(&str, &str)], broken: bool) -> Snapshot {
+        let location = json!({ "file": "app/pyproject.toml" });
+        let dependencies: Vec<_> = requirements
+            .iter()
+            .map(|(requirement, scope)| {
+                json!({
+                    "name": requirement.split(['=', '>', '<']).next().unwrap(),
+                    "version_spec": requirement,
+                    "project": "app",
+                    "scope": scope,
+                    "location": location,
+                })
+            })
+            .collect();
+        let diagnostics = if broken {
+            vec![json!({ "message": "expected `=`", "location": location })]
+        } else {
+            Vec::new()
+        };
+        serde_json::from_value(json!({
+            "schema_version": "1.0",
+            "projects": [{ "name": "app" }],
+            "dependencies": dependencies,
+            "graph": { "nodes": [], "edges": [] },
+            "findings": [],
+            "diagnostics": diagnostics,
+        }))
+        .unwrap()
+    }
+
+    fn changes(report: &HistoryReport, package: &str) -> Vec<(String, String, String, String)> {
+        let timeline = report.packages.iter().find(|t| t.package == package).unwrap();
+        timeline
+            .events
+            .iter()
+            .map(|e| (e.change.to_string(), e.scope.clone(), e.old.join(","), e.new.join(",")))
+            .collect()
+    }
+
+    #[test]
+    fn events_follow_each_requirement_and_scope() {
+        let mut timeline = Timeline::new(&[]);
+        timeline.record(&commit("a"), snapshot(&[("flask>=2", "main")], false));
+        timeline.record(
+            &commit("b"),
+            snapshot(&[("flask>=3", "main"), ("pytest>=7", "optional:test")], false),
+        );
+        timeline.record(&commit("c"), snapshot(&[("pytest>=7", "optional:test")], false));
+        let report = timeline.finish();
+
+        assert_eq!(report.commits, 3);
+        let event = |change: &str, scope: &str, old: &str, new: &str| {
+            (change.to_string(), scope.to_string(), old.to_string(), new.to_string())
+        };
+        assert_eq!(
+            changes(&report, "flask"),
+            vec![
+                event("added", "main", "", ">=2"),
+                event("changed", "main", ">=2", ">=3"),
+                event("removed", "main", ">=3", ""),
+            ]
+        );
+        assert_eq!(changes(&report, "pytest"), vec![event("added", "optional:test", "", ">=7")]);
+    }
+
+    #[test]
+    fn unparsable_manifest_keeps_its_requirements() {
+        let mut timeline = Timeline::new(&[]);
+        timeline.record(&commit("a"), snapshot(&[("numpy==1.26", "main")], false));
+        timeline.record(&commit("b"), snapshot(&[], true));
+        timeline.record(&commit("c"), snapshot(&[], true));
+        timeline.record(&commit("d"), snapshot(&[("numpy==2.0", "main")], false));
+        let report = timeline.finish();
+
+        let numpy = changes(&report, "numpy");
+        assert_eq!(numpy.len(), 2);
+        assert_eq!(numpy[1].0, "changed");
+        let commits: Vec<&str> = report.packages[0].events.iter().map(|e| &e.commit[..1]).collect();
+        assert_eq!(commits, vec!["a", "d"]);
+    }
+
+    #[test]
+    fn package_filter_compares_normalized_names() {
+        let mut timeline = Timeline::new(&["Scikit_Learn".to_string()]);
+        timeline.record(
+            &commit("a"),
+            snapshot(&[("scikit-learn>=1", "main"), ("numpy", "main")], false),
+        );
+        let report = timeline.finish();
+        assert_eq!(report.packages.len(), 1);
+        assert_eq!(report.packages[0].package, "scikit-learn");
+    }
+
+    #[test]
+    fn csv_keeps_scope_in_its_own_column_and_quotes_fields() {
+        let mut timeline = Timeline::new(&[]);
+        let mut quoted = commit("a");
+        quoted.summary = "Pin \"numpy\", drop torch".to_string();
+        timeline.record(&quoted, snapshot(&[("numpy>=1,<2", "group:ci")], false));
+        let csv = timeline.finish().render_csv();
+        let row = csv.lines().nth(1).unwrap();
+        assert!(row.starts_with("numpy,app,group:ci,added,,\">=1,<2\","), "{}", row);
+        assert!(row.ends_with(",\"Pin \"\"numpy\"\", drop torch\""), "{}", row);
+    }
+
+    #[test]
+    fn csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("line\nbreak"), "\"line\nbreak\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
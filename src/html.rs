@@ -0,0 +1,129 @@
+//! Self-contained interactive HTML report: the SVG graph, the findings and
+//! every dependency record in one file, with no external resources.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::report::{self, Report};
+use crate::svg::{self, escape};
+use crate::{DependencyAnalyzer, Finding, Severity, RULES};
+
+const TEMPLATE: &str = include_str!("html/report.html");
+const STYLE: &str = include_str!("html/report.css");
+const SCRIPT: &str = include_str!("html/report.js");
+
+/// Data embedded in the page for the script.
+#[derive(Serialize)]
+struct PageData {
+    report: Report,
+    rules: Vec<RuleEntry>,
+    /// Non-empty graph clusters in graph order, used for the category filter
+    categories: Vec<String>,
+    packages: BTreeMap<String, PackageStyle>,
+}
+
+#[derive(Serialize)]
+struct PackageStyle {
+    /// Label of the graph cluster the package is drawn in
+    category: String,
+    color: &'static str,
+    /// Part of a dependency cycle
+    cycle: bool,
+}
+
+#[derive(Serialize)]
+struct RuleEntry {
+    id: &'static str,
+    description: &'static str,
+    severity: Severity,
+}
+
+/// Render the HTML report for `analyzer` and the reported `findings`.
+pub fn render(analyzer: &DependencyAnalyzer, findings: &[&Finding]) -> Result<String> {
+    let mut categories = Vec::new();
+    let mut packages = BTreeMap::new();
+    let cycles = analyzer.cycle_membership();
+    for (_, label, members) in analyzer.graph_clusters() {
+        if members.is_empty() {
+            continue;
+        }
+        for node in members {
+            let name = &analyzer.graph[node];
+            let style = PackageStyle {
+                category: label.clone(),
+                color: analyzer.node_style(name).0,
+                cycle: cycles.contains(node),
+            };
+            packages.insert(name.clone(), style);
+            // Packages folded into a collapsed cluster filter with it
+            for member in analyzer.collapsed_members(name) {
+                let style = PackageStyle {
+                    category: label.clone(),
+                    color: analyzer.node_style(member).0,
+                    cycle: false,
+                };
+                packages.insert(member.to_string(), style);
+            }
+        }
+        categories.push(label);
+    }
+    let data = PageData {
+        report: report::build_report(analyzer, findings),
+        rules: RULES
+            .iter()
+            .map(|rule| RuleEntry {
+                id: rule.id,
+                description: rule.description,
+                severity: rule.severity,
+            })
+            .collect(),
+        categories,
+        packages,
+    };
+    // `</` would end the script element early
+    let json = serde_json::to_string(&data)?.replace("</", "<\\/");
+
+    let title = escape(&format!("Dependency report: {}", analyzer.root.display()));
+    let graph = svg::render(analyzer);
+    Ok(fill(
+        TEMPLATE,
+        &[
+            ("title", &title),
+            ("style", STYLE),
+            ("graph", &graph),
+            ("data", &json),
+            ("script", SCRIPT),
+        ],
+    ))
+}
+
+/// Replace `{{key}}` placeholders in a single pass, so inserted values are
+/// never scanned for placeholders themselves.
+fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let value = after.find("}}").and_then(|end| {
+            let key = &after[..end];
+            values
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| (*v, end + 2))
+        });
+        match value {
+            Some((value, consumed)) => {
+                output.push_str(value);
+                rest = &after[consumed..];
+            }
+            None => {
+                output.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
@@ -0,0 +1,447 @@
+(function () {
+  "use strict";
+
+  var data = JSON.parse(document.getElementById("report-data").textContent);
+  var report = data.report;
+  var SEVERITIES = ["error", "warning", "info"];
+  var SEVERITY_RANK = { error: 0, warning: 1, info: 2 };
+
+  var state = {
+    query: "",
+    categories: new Set(data.categories),
+    severities: new Set(SEVERITIES),
+    sortKey: "severity",
+    sortAsc: true,
+    selected: null
+  };
+
+  // Lookup tables
+  var requirements = {};
+  report.dependencies.forEach(function (dep) {
+    (requirements[dep.name] = requirements[dep.name] || []).push(dep);
+  });
+  var dependsOn = {};
+  var requiredBy = {};
+  report.graph.edges.forEach(function (edge) {
+    (dependsOn[edge.from] = dependsOn[edge.from] || []).push(edge.to);
+    (requiredBy[edge.to] = requiredBy[edge.to] || []).push(edge.from);
+  });
+  var rules = {};
+  data.rules.forEach(function (rule) { rules[rule.id] = rule; });
+
+  function el(tag, attrs, children) {
+    var node = document.createElement(tag);
+    Object.keys(attrs || {}).forEach(function (key) {
+      if (key === "text") {
+        node.textContent = attrs[key];
+      } else if (key === "className") {
+        node.className = attrs[key];
+      } else {
+        node.setAttribute(key, attrs[key]);
+      }
+    });
+    (children || []).forEach(function (child) {
+      node.appendChild(typeof child === "string" ? document.createTextNode(child) : child);
+    });
+    return node;
+  }
+
+  function formatLocation(location) {
+    if (!location) {
+      return "";
+    }
+    return location.line ? location.file + ":" + location.line : location.file;
+  }
+
+  function categoryOf(name) {
+    var style = data.packages[name];
+    return style ? style.category : "Other";
+  }
+
+  function packageLink(name) {
+    var link = el("a", { className: "package", text: name, href: "#" });
+    link.addEventListener("click", function (event) {
+      event.preventDefault();
+      select(name, true);
+    });
+    return link;
+  }
+
+  // Summary
+  (function () {
+    var summary = document.getElementById("summary");
+    var counts = { error: 0, warning: 0, info: 0 };
+    report.findings.forEach(function (finding) { counts[finding.severity] += 1; });
+    [
+      report.graph.nodes.length + " packages",
+      report.dependencies.length + " requirements",
+      report.projects.length + " projects",
+      counts.error + " errors",
+      counts.warning + " warnings",
+      counts.info + " info"
+    ].forEach(function (text) {
+      summary.appendChild(el("span", { text: text }));
+    });
+  })();
+
+  // Filters
+  function checkbox(container, value, label, set) {
+    var input = el("input", { type: "checkbox", value: value });
+    input.checked = true;
+    input.addEventListener("change", function () {
+      if (input.checked) {
+        set.add(value);
+      } else {
+        set.delete(value);
+      }
+      update();
+    });
+    container.appendChild(el("label", {}, [input, " " + label]));
+  }
+  var categoryFilters = document.getElementById("category-filters");
+  data.categories.forEach(function (category) {
+    checkbox(categoryFilters, category, category, state.categories);
+  });
+  var severityFilters = document.getElementById("severity-filters");
+  SEVERITIES.forEach(function (severity) {
+    checkbox(severityFilters, severity, severity, state.severities);
+  });
+  var search = document.getElementById("search");
+  search.addEventListener("input", function () {
+    state.query = search.value.trim().toLowerCase();
+    update();
+  });
+  search.addEventListener("keydown", function (event) {
+    if (event.key !== "Enter" || !state.query) {
+      return;
+    }
+    var match = report.graph.nodes.find(function (node) {
+      return node.id.toLowerCase().indexOf(state.query) !== -1 &&
+        state.categories.has(categoryOf(node.id));
+    });
+    if (match) {
+      select(match.id, true);
+    }
+  });
+
+  // Graph: pan and zoom by rewriting the viewBox
+  var container = document.getElementById("graph");
+  var svg = container.querySelector("svg");
+  var full = svg.viewBox.baseVal;
+  var initial = { x: full.x, y: full.y, width: full.width, height: full.height };
+  var view = Object.assign({}, initial);
+  svg.removeAttribute("width");
+  svg.removeAttribute("height");
+  svg.setAttribute("preserveAspectRatio", "xMidYMid meet");
+
+  function applyView() {
+    svg.setAttribute("viewBox", [view.x, view.y, view.width, view.height].join(" "));
+  }
+
+  function svgPoint(clientX, clientY) {
+    var point = svg.createSVGPoint();
+    point.x = clientX;
+    point.y = clientY;
+    return point.matrixTransform(svg.getScreenCTM().inverse());
+  }
+
+  function zoom(factor, center) {
+    var width = Math.min(Math.max(view.width * factor, initial.width / 20), initial.width * 4);
+    var scale = width / view.width;
+    center = center || { x: view.x + view.width / 2, y: view.y + view.height / 2 };
+    view.x = center.x - (center.x - view.x) * scale;
+    view.y = center.y - (center.y - view.y) * scale;
+    view.width *= scale;
+    view.height *= scale;
+    applyView();
+  }
+
+  function centerOn(name) {
+    var node = nodeElement(name);
+    if (!node) {
+      return;
+    }
+    var box = node.getBBox();
+    view.x = box.x + box.width / 2 - view.width / 2;
+    view.y = box.y + box.height / 2 - view.height / 2;
+    applyView();
+  }
+
+  container.addEventListener("wheel", function (event) {
+    event.preventDefault();
+    zoom(event.deltaY > 0 ? 1.15 : 1 / 1.15, svgPoint(event.clientX, event.clientY));
+  }, { passive: false });
+
+  var drag = null;
+  var suppressClick = false;
+  container.addEventListener("mousedown", function (event) {
+    drag = { x: event.clientX, y: event.clientY, view: Object.assign({}, view), moved: false };
+    container.classList.add("dragging");
+  });
+  window.addEventListener("mousemove", function (event) {
+    if (!drag) {
+      return;
+    }
+    var ctm = svg.getScreenCTM();
+    var dx = (event.clientX - drag.x) / ctm.a;
+    var dy = (event.clientY - drag.y) / ctm.d;
+    if (Math.abs(event.clientX - drag.x) + Math.abs(event.clientY - drag.y) > 3) {
+      drag.moved = true;
+    }
+    view.x = drag.view.x - dx;
+    view.y = drag.view.y - dy;
+    applyView();
+  });
+  window.addEventListener("mouseup", function () {
+    // The click that follows a drag should not select a package
+    suppressClick = drag !== null && drag.moved;
+    drag = null;
+    container.classList.remove("dragging");
+  });
+
+  document.getElementById("zoom-in").addEventListener("click", function () { zoom(1 / 1.25); });
+  document.getElementById("zoom-out").addEventListener("click", function () { zoom(1.25); });
+  document.getElementById("zoom-reset").addEventListener("click", function () {
+    view = Object.assign({}, initial);
+    applyView();
+  });
+
+  var nodeElements = {};
+  svg.querySelectorAll(".node").forEach(function (node) {
+    nodeElements[node.getAttribute("data-package")] = node;
+    node.addEventListener("click", function () {
+      if (!suppressClick) {
+        select(node.getAttribute("data-package"), false);
+      }
+    });
+  });
+  var edgeElements = Array.prototype.slice.call(svg.querySelectorAll(".edge"));
+
+  function nodeElement(name) {
+    return nodeElements[name] || null;
+  }
+
+  function updateGraph() {
+    Object.keys(nodeElements).forEach(function (name) {
+      var node = nodeElements[name];
+      var visible = state.categories.has(categoryOf(name));
+      var match = state.query !== "" && name.toLowerCase().indexOf(state.query) !== -1;
+      node.classList.toggle("hidden", !visible);
+      node.classList.toggle("match", match);
+      node.classList.toggle("dimmed", state.query !== "" && !match);
+      node.classList.toggle("selected", name === state.selected);
+    });
+    edgeElements.forEach(function (edge) {
+      var from = edge.getAttribute("data-from");
+      var to = edge.getAttribute("data-to");
+      var visible = state.categories.has(categoryOf(from)) && state.categories.has(categoryOf(to));
+      edge.classList.toggle("hidden", !visible);
+      edge.classList.toggle("dimmed", state.query !== "" &&
+        from.toLowerCase().indexOf(state.query) === -1 &&
+        to.toLowerCase().indexOf(state.query) === -1);
+    });
+  }
+
+  // Findings table
+  function findingVisible(finding) {
+    if (!state.severities.has(finding.severity)) {
+      return false;
+    }
+    // Packages a graph view left out do not hide the finding
+    var shown = finding.packages.filter(function (name) { return data.packages[name]; });
+    if (shown.length > 0 && !shown.some(function (name) {
+      return state.categories.has(categoryOf(name));
+    })) {
+      return false;
+    }
+    if (state.query === "") {
+      return true;
+    }
+    var text = [finding.rule, finding.message].concat(finding.packages).join(" ").toLowerCase();
+    return text.indexOf(state.query) !== -1;
+  }
+
+  function sortValue(finding, key) {
+    switch (key) {
+      case "severity": return SEVERITY_RANK[finding.severity];
+      case "packages": return finding.packages.join(", ");
+      case "location": return formatLocation(finding.locations[0]);
+      default: return finding[key];
+    }
+  }
+
+  function compare(a, b) {
+    var x = sortValue(a, state.sortKey);
+    var y = sortValue(b, state.sortKey);
+    var order = x < y ? -1 : x > y ? 1 : 0;
+    if (order === 0) {
+      order = a.message < b.message ? -1 : a.message > b.message ? 1 : 0;
+    }
+    return state.sortAsc ? order : -order;
+  }
+
+  var headers = document.querySelectorAll("#findings th");
+  headers.forEach(function (header) {
+    header.addEventListener("click", function () {
+      var key = header.getAttribute("data-key");
+      state.sortAsc = state.sortKey === key ? !state.sortAsc : true;
+      state.sortKey = key;
+      updateFindings();
+    });
+  });
+
+  function updateFindings() {
+    headers.forEach(function (header) {
+      var active = header.getAttribute("data-key") === state.sortKey;
+      header.classList.toggle("asc", active && state.sortAsc);
+      header.classList.toggle("desc", active && !state.sortAsc);
+    });
+    var body = document.querySelector("#findings tbody");
+    body.textContent = "";
+    var visible = report.findings.filter(findingVisible).sort(compare);
+    visible.forEach(function (finding) {
+      var packages = el("td");
+      finding.packages.forEach(function (name, i) {
+        if (i > 0) {
+          packages.appendChild(document.createTextNode(", "));
+        }
+        packages.appendChild(packageLink(name));
+      });
+      var locations = el("td");
+      finding.locations.forEach(function (location, i) {
+        if (i > 0) {
+          locations.appendChild(el("br"));
+        }
+        locations.appendChild(el("code", { text: formatLocation(location) }));
+      });
+      var rule = rules[finding.rule];
+      body.appendChild(el("tr", {}, [
+        el("td", {}, [el("span", {
+          className: "severity severity-" + finding.severity,
+          text: finding.severity
+        })]),
+        el("td", {}, [el("code", { text: finding.rule, title: rule ? rule.description : "" })]),
+        packages,
+        el("td", { text: finding.message }),
+        locations
+      ]));
+    });
+    document.getElementById("findings-count").textContent =
+      "(" + visible.length + " of " + report.findings.length + ")";
+    document.getElementById("no-findings").hidden = visible.length > 0;
+  }
+
+  // Package details
+  function section(title, content) {
+    return [el("h3", { text: title }), content];
+  }
+
+  function packageList(names) {
+    if (!names || names.length === 0) {
+      return el("p", { className: "placeholder", text: "None" });
+    }
+    return el("ul", {}, names.slice().sort().map(function (name) {
+      return el("li", {}, [packageLink(name)]);
+    }));
+  }
+
+  function renderDetails(name) {
+    var details = document.getElementById("details");
+    details.textContent = "";
+    var style = data.packages[name] || { category: "Other", color: "#2D3436" };
+    var node = report.graph.nodes.find(function (n) { return n.id === name; });
+    details.appendChild(el("h2", {}, [
+      el("span", { className: "swatch", style: "background:" + style.color }),
+      name
+    ]));
+    details.appendChild(el("p", {
+      text: style.category + (node && node.local ? " (local project)" : "")
+    }));
+    if (style.cycle) {
+      details.appendChild(el("p", {
+        className: "cycle-note",
+        text: "Part of a dependency cycle"
+      }));
+    }
+
+    var deps = requirements[name] || [];
+    var rows = deps.map(function (dep) {
+      return el("tr", {}, [
+        el("td", {}, [el("code", { text: dep.version_spec || "*" })]),
+        el("td", {}, [el("code", { text: dep.version_req || "" })]),
+        el("td", { text: dep.resolved_version || "" }),
+        el("td", { text: dep.scope }),
+        el("td", { text: dep.project }),
+        el("td", {}, [el("code", { text: formatLocation(dep.location) })])
+      ]);
+    });
+    var table = el("table", {}, [
+      el("thead", {}, [el("tr", {}, ["Spec", "Range", "Locked", "Scope", "Project", "Source"]
+        .map(function (text) { return el("th", { text: text }); }))]),
+      el("tbody", {}, rows)
+    ]);
+    section("Requirements (" + deps.length + ")", deps.length ? table :
+      el("p", { className: "placeholder", text: "None" })).forEach(function (n) {
+      details.appendChild(n);
+    });
+
+    var files = [];
+    deps.forEach(function (dep) {
+      if (files.indexOf(dep.location.file) === -1) {
+        files.push(dep.location.file);
+      }
+    });
+    section("Source files", el("ul", {}, files.sort().map(function (file) {
+      return el("li", {}, [el("code", { text: file })]);
+    }))).forEach(function (n) { details.appendChild(n); });
+
+    section("Depends on", packageList(dependsOn[name])).forEach(function (n) {
+      details.appendChild(n);
+    });
+    section("Required by", packageList(requiredBy[name])).forEach(function (n) {
+      details.appendChild(n);
+    });
+
+    var findings = report.findings.filter(function (finding) {
+      return finding.packages.indexOf(name) !== -1;
+    });
+    section("Findings (" + findings.length + ")", findings.length ? el("ul", {},
+      findings.map(function (finding) {
+        return el("li", {}, [
+          el("span", { className: "severity severity-" + finding.severity, text: finding.severity }),
+          " " + finding.message
+        ]);
+      })) : el("p", { className: "placeholder", text: "None" })).forEach(function (n) {
+      details.appendChild(n);
+    });
+  }
+
+  function select(name, center) {
+    state.selected = name;
+    renderDetails(name);
+    updateGraph();
+    if (center) {
+      centerOn(name);
+    }
+  }
+
+  // Parse errors
+  if (report.diagnostics.length > 0) {
+    var diagnostics = document.getElementById("diagnostics");
+    diagnostics.hidden = false;
+    var list = diagnostics.querySelector("ul");
+    report.diagnostics.forEach(function (diagnostic) {
+      list.appendChild(el("li", {}, [
+        el("code", { text: formatLocation(diagnostic.location) }),
+        " " + diagnostic.message
+      ]));
+    });
+  }
+
+  function update() {
+    updateGraph();
+    updateFindings();
+  }
+
+  update();
+})();
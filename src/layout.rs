@@ -0,0 +1,377 @@
+//! Layered (Sugiyama-style) graph layout, left to right like `rankdir=LR`.
+//!
+//! 1. Cycles are broken by reversing DFS back edges.
+//! 2. Nodes are assigned to layers by longest path from the sources.
+//! 3. Edges spanning several layers get a dummy node per crossed layer.
+//! 4. Barycenter sweeps reorder each layer to reduce crossings. Nodes stay
+//!    grouped by band so every cluster occupies its own horizontal strip.
+//! 5. Layers become columns; bands stack vertically.
+
+use std::collections::HashMap;
+
+pub const LAYER_GAP: f64 = 80.0;
+pub const ROW_HEIGHT: f64 = 56.0;
+pub const BAND_GAP: f64 = 40.0;
+/// Space reserved above the nodes of a band for its label.
+pub const BAND_HEADER: f64 = 24.0;
+pub const MARGIN: f64 = 20.0;
+
+/// A node to lay out.
+pub struct LayoutNode {
+    pub width: f64,
+    pub height: f64,
+    /// Index of the band (cluster) the node belongs to
+    pub band: usize,
+}
+
+/// Position of a node: top-left corner.
+#[derive(Debug, Clone, Copy)]
+pub struct Placement {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Vertical extent of a band and the horizontal extent of its nodes.
+#[derive(Debug, Clone, Copy)]
+pub struct BandBox {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+pub struct Layout {
+    pub nodes: Vec<Placement>,
+    /// Polyline of each input edge from the source's right side to the
+    /// target's left side, in input order
+    pub edges: Vec<Vec<(f64, f64)>>,
+    /// Box of each band that has nodes, by band index
+    pub bands: HashMap<usize, BandBox>,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Lay out `nodes` connected by `edges` (pairs of node indices).
+pub fn layout(nodes: &[LayoutNode], edges: &[(usize, usize)]) -> Layout {
+    let n = nodes.len();
+
+    // 1. Cycle removal
+    let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for (i, &(from, to)) in edges.iter().enumerate() {
+        if from != to {
+            adjacency[from].push(i);
+        }
+    }
+    let mut reversed = vec![false; edges.len()];
+    let mut state = vec![0u8; n]; // 0 = new, 1 = on stack, 2 = done
+    for start in 0..n {
+        if state[start] != 0 {
+            continue;
+        }
+        let mut stack = vec![(start, 0usize)];
+        state[start] = 1;
+        while let Some((node, next)) = stack.pop() {
+            if let Some(&edge) = adjacency[node].get(next) {
+                stack.push((node, next + 1));
+                let target = edges[edge].1;
+                match state[target] {
+                    0 => {
+                        state[target] = 1;
+                        stack.push((target, 0));
+                    }
+                    1 => reversed[edge] = true,
+                    _ => {}
+                }
+            } else {
+                state[node] = 2;
+            }
+        }
+    }
+    let directed: Vec<(usize, usize)> = edges
+        .iter()
+        .zip(&reversed)
+        .filter(|((from, to), _)| from != to)
+        .map(|(&(from, to), &rev)| if rev { (to, from) } else { (from, to) })
+        .collect();
+
+    // 2. Longest-path layering
+    let mut layer = vec![0usize; n];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &(from, to) in &directed {
+            if layer[to] < layer[from] + 1 {
+                layer[to] = layer[from] + 1;
+                changed = true;
+            }
+        }
+    }
+
+    // 3. Dummy nodes; virtual nodes after the real ones
+    let mut band: Vec<usize> = nodes.iter().map(|node| node.band).collect();
+    let mut segments: Vec<(usize, usize)> = Vec::new();
+    // Chain of virtual node ids for each directed edge
+    let mut chains: Vec<Vec<usize>> = Vec::new();
+    for &(from, to) in &directed {
+        let mut chain = vec![from];
+        let mut previous = from;
+        for l in layer[from] + 1..layer[to] {
+            let dummy = layer.len();
+            layer.push(l);
+            band.push(band[from]);
+            segments.push((previous, dummy));
+            chain.push(dummy);
+            previous = dummy;
+        }
+        segments.push((previous, to));
+        chain.push(to);
+        chains.push(chain);
+    }
+    let total = layer.len();
+
+    // 4. Ordering within layers
+    let layer_count = layer.iter().max().map_or(0, |m| m + 1);
+    let mut layers: Vec<Vec<usize>> = vec![Vec::new(); layer_count];
+    for v in 0..total {
+        layers[layer[v]].push(v);
+    }
+    let mut position = vec![0f64; total];
+    let sort_layer = |members: &mut Vec<usize>, position: &mut Vec<f64>, key: &dyn Fn(usize) -> f64| {
+        members.sort_by(|&a, &b| {
+            band[a]
+                .cmp(&band[b])
+                .then(key(a).total_cmp(&key(b)))
+                .then(a.cmp(&b))
+        });
+        for (i, &v) in members.iter().enumerate() {
+            position[v] = i as f64;
+        }
+    };
+    for members in &mut layers {
+        sort_layer(members, &mut position, &|v| v as f64);
+    }
+
+    let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); total];
+    let mut successors: Vec<Vec<usize>> = vec![Vec::new(); total];
+    for &(from, to) in &segments {
+        successors[from].push(to);
+        predecessors[to].push(from);
+    }
+    let barycenter = |neighbors: &[usize], position: &[f64], own: f64| {
+        if neighbors.is_empty() {
+            own
+        } else {
+            neighbors.iter().map(|&u| position[u]).sum::<f64>() / neighbors.len() as f64
+        }
+    };
+    for sweep in 0..8 {
+        let down = sweep % 2 == 0;
+        let order: Vec<usize> = if down {
+            (1..layer_count).collect()
+        } else {
+            (0..layer_count.saturating_sub(1)).rev().collect()
+        };
+        for l in order {
+            let snapshot = position.clone();
+            let keys: HashMap<usize, f64> = layers[l]
+                .iter()
+                .map(|&v| {
+                    let neighbors = if down { &predecessors[v] } else { &successors[v] };
+                    (v, barycenter(neighbors, &snapshot, snapshot[v]))
+                })
+                .collect();
+            sort_layer(&mut layers[l], &mut position, &|v| keys[&v]);
+        }
+    }
+
+    // 5. Coordinates: bands stacked vertically, sized for their fullest layer
+    let band_count = band.iter().max().map_or(0, |m| m + 1);
+    let mut band_rows = vec![0usize; band_count];
+    for members in &layers {
+        let mut counts = vec![0usize; band_count];
+        for &v in members {
+            counts[band[v]] += 1;
+        }
+        for (b, count) in counts.into_iter().enumerate() {
+            band_rows[b] = band_rows[b].max(count);
+        }
+    }
+    let mut band_top = vec![0f64; band_count];
+    let mut y = MARGIN;
+    for b in 0..band_count {
+        band_top[b] = y;
+        if band_rows[b] > 0 {
+            y += BAND_HEADER + band_rows[b] as f64 * ROW_HEIGHT + BAND_GAP;
+        }
+    }
+    let height = (y - BAND_GAP + MARGIN).max(2.0 * MARGIN);
+
+    let layer_width: Vec<f64> = layers
+        .iter()
+        .map(|members| {
+            members
+                .iter()
+                .filter(|&&v| v < n)
+                .map(|&v| nodes[v].width)
+                .fold(0.0, f64::max)
+        })
+        .collect();
+    let mut layer_x = Vec::with_capacity(layer_count);
+    let mut x = MARGIN + BAND_GAP / 2.0;
+    for width in &layer_width {
+        layer_x.push(x);
+        x += width + LAYER_GAP;
+    }
+    let width = x - LAYER_GAP + MARGIN + BAND_GAP / 2.0;
+
+    // Center each layer's nodes inside their band; v's center line
+    let mut center = vec![(0f64, 0f64); total];
+    for (l, members) in layers.iter().enumerate() {
+        for b in 0..band_count {
+            let in_band: Vec<usize> = members.iter().copied().filter(|&v| band[v] == b).collect();
+            let offset = (band_rows[b] - in_band.len()) as f64 * ROW_HEIGHT / 2.0;
+            for (i, &v) in in_band.iter().enumerate() {
+                let cy = band_top[b] + BAND_HEADER + offset + (i as f64 + 0.5) * ROW_HEIGHT;
+                let cx = layer_x[l] + layer_width[l] / 2.0;
+                center[v] = (cx, cy);
+            }
+        }
+    }
+
+    let placements: Vec<Placement> = (0..n)
+        .map(|v| Placement {
+            x: center[v].0 - nodes[v].width / 2.0,
+            y: center[v].1 - nodes[v].height / 2.0,
+        })
+        .collect();
+
+    // Edge polylines, restored to their original direction
+    let mut chain_of = chains.into_iter();
+    let edge_points: Vec<Vec<(f64, f64)>> = edges
+        .iter()
+        .zip(&reversed)
+        .map(|(&(from, to), &rev)| {
+            if from == to {
+                return Vec::new();
+            }
+            let chain = chain_of.next().unwrap_or_default();
+            let mut points: Vec<(f64, f64)> = Vec::with_capacity(chain.len());
+            for (i, &v) in chain.iter().enumerate() {
+                let (cx, cy) = center[v];
+                if v < n {
+                    // Leave from the right side, enter from the left
+                    let half = nodes[v].width / 2.0;
+                    points.push(if i == 0 { (cx + half, cy) } else { (cx - half, cy) });
+                } else {
+                    points.push((cx, cy));
+                }
+            }
+            if rev {
+                points.reverse();
+            }
+            points
+        })
+        .collect();
+
+    let mut bands = HashMap::new();
+    for b in 0..band_count {
+        let members: Vec<usize> = (0..n).filter(|&v| band[v] == b).collect();
+        if members.is_empty() {
+            continue;
+        }
+        let left = members.iter().map(|&v| placements[v].x).fold(f64::MAX, f64::min);
+        let right = members
+            .iter()
+            .map(|&v| placements[v].x + nodes[v].width)
+            .fold(f64::MIN, f64::max);
+        bands.insert(
+            b,
+            BandBox {
+                x: left - BAND_GAP / 2.0,
+                y: band_top[b],
+                width: right - left + BAND_GAP,
+                height: BAND_HEADER + band_rows[b] as f64 * ROW_HEIGHT,
+            },
+        );
+    }
+
+    Layout {
+        nodes: placements,
+        edges: edge_points,
+        bands,
+        width,
+        height,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nodes(bands: &[usize]) -> Vec<LayoutNode> {
+        bands
+            .iter()
+            .map(|&band| LayoutNode {
+                width: 60.0,
+                height: 30.0,
+                band,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn layers_follow_the_longest_path() {
+        // a -> b -> c, plus a -> c spanning two layers
+        let layout = layout(&nodes(&[0, 0, 0]), &[(0, 1), (1, 2), (0, 2)]);
+        let x: Vec<f64> = layout.nodes.iter().map(|p| p.x).collect();
+        assert!(x[0] < x[1] && x[1] < x[2], "{:?}", x);
+
+        // The long edge bends through a dummy node in the middle layer
+        let long = &layout.edges[2];
+        assert_eq!(long.len(), 3);
+        assert_eq!(long[0], (x[0] + 60.0, layout.nodes[0].y + 15.0));
+        assert_eq!(long[2], (x[2], layout.nodes[2].y + 15.0));
+        assert!(layout.width >= x[2] + 60.0 + MARGIN);
+    }
+
+    #[test]
+    fn cycles_are_laid_out_with_edges_in_their_original_direction() {
+        let layout = layout(&nodes(&[0, 0, 0]), &[(0, 1), (1, 0), (2, 2)]);
+        let (a, b) = (layout.nodes[0], layout.nodes[1]);
+        assert!(a.x < b.x);
+        // b -> a was reversed for layering and still starts at b
+        assert_eq!(layout.edges[1], [(b.x, b.y + 15.0), (a.x + 60.0, a.y + 15.0)]);
+        assert!(layout.edges[2].is_empty(), "self-loops have no polyline");
+    }
+
+    #[test]
+    fn barycenter_sweeps_remove_avoidable_crossings() {
+        // a -> x and b -> y, with x listed after y
+        let layout = layout(&nodes(&[0, 0, 0, 0]), &[(0, 3), (1, 2)]);
+        let y: Vec<f64> = layout.nodes.iter().map(|p| p.y).collect();
+        assert!(y[0] < y[1]);
+        assert!(y[3] < y[2], "x moves above y: {:?}", y);
+    }
+
+    #[test]
+    fn bands_stack_vertically_and_enclose_their_nodes() {
+        let layout = layout(&nodes(&[1, 0, 1, 3]), &[(0, 1), (1, 2)]);
+        assert_eq!(layout.bands.len(), 3, "band 2 has no nodes");
+        let top = |b: usize| layout.bands[&b].y;
+        assert!(top(0) < top(1) && top(1) < top(3));
+        for (node, band) in layout.nodes.iter().zip([1, 0, 1, 3]) {
+            let bx = layout.bands[&band];
+            assert!(node.x >= bx.x && node.x + 60.0 <= bx.x + bx.width);
+            assert!(node.y >= bx.y + BAND_HEADER && node.y + 30.0 <= bx.y + bx.height);
+        }
+        let last = layout.bands[&3];
+        assert_eq!(layout.height, last.y + last.height + MARGIN);
+    }
+
+    #[test]
+    fn empty_graph_has_a_margin_only_canvas() {
+        let layout = layout(&[], &[]);
+        assert!(layout.nodes.is_empty() && layout.bands.is_empty());
+        assert_eq!(layout.height, 2.0 * MARGIN);
+    }
+}
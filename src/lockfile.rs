@@ -0,0 +1,139 @@
+//! Resolved versions and artifact hashes from lockfiles next to the
+//! manifests: Pipfile.lock, poetry.lock, uv.lock and pdm.lock.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::vfs::FileSystem;
+
+/// Lockfile names looked up in every project root.
+pub const LOCKFILE_NAMES: &[&str] = &["uv.lock", "poetry.lock", "pdm.lock", "Pipfile.lock"];
+
+/// A package pinned by a lockfile.
+#[derive(Debug, Clone)]
+pub struct LockedPackage {
+    pub version: String,
+    /// `(algorithm, hex digest)` pairs, e.g. `("sha256", "ab12...")`
+    pub hashes: Vec<(String, String)>,
+}
+
+/// Normalized distribution name (PEP 503), so `Foo_Bar` matches `foo-bar`.
+pub fn normalize_name(name: &str) -> String {
+    let mut normalized = String::with_capacity(name.len());
+    let mut separator = false;
+    for c in name.trim().chars() {
+        if matches!(c, '-' | '_' | '.') {
+            separator = true;
+        } else {
+            if separator && !normalized.is_empty() {
+                normalized.push('-');
+            }
+            separator = false;
+            normalized.extend(c.to_lowercase());
+        }
+    }
+    normalized
+}
+
+/// Load the lockfile at `path`, keyed by normalized package name.
+pub fn load(path: &Path, files: &FileSystem) -> Result<BTreeMap<String, LockedPackage>> {
+    let content = files
+        .read_to_string(path)
+        .with_context(|| format!("Failed to read lockfile {}", path.display()))?;
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let packages = if file_name == "Pipfile.lock" {
+        parse_pipfile_lock(&content, path)?
+    } else {
+        parse_toml_lock(&content, path)?
+    };
+    Ok(packages)
+}
+
+/// Pipfile.lock: `{"default": {"name": {"version": "==1.0", "hashes": [...]}}}`
+fn parse_pipfile_lock(content: &str, path: &Path) -> Result<BTreeMap<String, LockedPackage>> {
+    let lock: serde_json::Value = serde_json::from_str(content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    let mut packages = BTreeMap::new();
+    for section in ["default", "develop"] {
+        let Some(entries) = lock.get(section).and_then(|s| s.as_object()) else {
+            continue;
+        };
+        for (name, entry) in entries {
+            let Some(version) = entry.get("version").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            let hashes = entry
+                .get("hashes")
+                .and_then(|h| h.as_array())
+                .map(|h| h.iter().filter_map(|h| h.as_str()).filter_map(split_hash).collect())
+                .unwrap_or_default();
+            packages
+                .entry(normalize_name(name))
+                .or_insert_with(|| LockedPackage {
+                    version: version.trim_start_matches("==").to_string(),
+                    hashes,
+                });
+        }
+    }
+    Ok(packages)
+}
+
+/// poetry.lock, uv.lock and pdm.lock all use `[[package]]` tables with
+/// `name` and `version`; hashes live in `files`, `sdist`/`wheels` or, for
+/// old Poetry versions, in `[metadata.files]`.
+fn parse_toml_lock(content: &str, path: &Path) -> Result<BTreeMap<String, LockedPackage>> {
+    let lock: toml::Value = toml::from_str(content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    let legacy_files = lock
+        .get("metadata")
+        .and_then(|m| m.get("files"))
+        .and_then(|f| f.as_table());
+
+    let mut packages = BTreeMap::new();
+    let entries = lock.get("package").and_then(|p| p.as_array());
+    for entry in entries.into_iter().flatten() {
+        let (Some(name), Some(version)) = (
+            entry.get("name").and_then(|n| n.as_str()),
+            entry.get("version").and_then(|v| v.as_str()),
+        ) else {
+            continue;
+        };
+
+        let mut artifacts: Vec<&toml::Value> = Vec::new();
+        for key in ["files", "wheels"] {
+            if let Some(files) = entry.get(key).and_then(|f| f.as_array()) {
+                artifacts.extend(files);
+            }
+        }
+        if let Some(sdist) = entry.get("sdist") {
+            artifacts.push(sdist);
+        }
+        if let Some(files) = legacy_files.and_then(|f| f.get(name)).and_then(|f| f.as_array()) {
+            artifacts.extend(files);
+        }
+        let mut hashes: Vec<(String, String)> = artifacts
+            .iter()
+            .filter_map(|a| a.get("hash").and_then(|h| h.as_str()))
+            .filter_map(split_hash)
+            .collect();
+        hashes.sort();
+        hashes.dedup();
+
+        packages
+            .entry(normalize_name(name))
+            .or_insert_with(|| LockedPackage {
+                version: version.to_string(),
+                hashes,
+            });
+    }
+    Ok(packages)
+}
+
+fn split_hash(hash: &str) -> Option<(String, String)> {
+    let (algorithm, digest) = hash.split_once(':')?;
+    Some((algorithm.to_lowercase(), digest.to_string()))
+}
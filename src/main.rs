@@ -1,26 +1,143 @@
+mod baseline;
+mod classifiers;
+mod config;
+mod consistency;
+mod diff;
+mod git;
+mod graph_export;
+mod history;
+mod html;
+mod layout;
+mod lockfile;
+mod metrics;
+mod project;
+mod report;
+mod sarif;
+mod sbom;
+mod sbom_import;
+mod specifier;
+mod svg;
+mod table;
+mod tree;
+mod vfs;
+mod view;
+mod walk;
+mod why;
+
 use anyhow::{Context, Result};
-use clap::Parser;
+use baseline::Baseline;
+use clap::{Parser, Subcommand};
 use colored::*;
+use config::{Config, ConflictRule};
+use globset::GlobSet;
+use lockfile::LockedPackage;
 use petgraph::graph::{DiGraph, NodeIndex};
+use project::{Project, Workspace};
 use regex::Regex;
 use semver::{Version, VersionReq};
-use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
 use std::fs;
-use std::path::Path;
-use walkdir::WalkDir;
-use which;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::LazyLock;
+use vfs::{FileSystem, GitTree};
+use walk::WalkEvent;
+
+/// Set when a machine-readable report is written to stdout.
+static STDOUT_IS_REPORT: AtomicBool = AtomicBool::new(false);
+
+/// Set while scanning many revisions, where per-file progress would bury
+/// everything else.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Print a progress or summary message. These go to stderr while stdout
+/// carries a machine-readable report, so the report can be piped as is.
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if !QUIET.load(Ordering::Relaxed) {
+            if STDOUT_IS_REPORT.load(Ordering::Relaxed) {
+                eprintln!($($arg)*)
+            } else {
+                println!($($arg)*)
+            }
+        }
+    };
+}
+
+/// Formats whose output is meant for other programs rather than people.
+fn is_machine_readable(format: &str) -> bool {
+    matches!(format, "json" | "sarif")
+        || sbom::FORMATS.contains(&format)
+        || graph_export::FORMATS.contains(&format)
+}
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Path to Python project
+    /// Path to Python project (required unless --sbom is given)
+    #[arg(short, long, required_unless_present = "sbom")]
+    path: Option<String>,
+
+    /// Read the manifests under --path from this git revision instead of
+    /// the working tree, without checking it out
+    #[arg(long, value_name = "REV", requires = "path")]
+    rev: Option<String>,
+
+    /// CycloneDX or SPDX document to analyze alongside (or instead of) the
+    /// scanned project; may be repeated
+    #[arg(long = "sbom", value_name = "FILE")]
+    sbom: Vec<String>,
+
+    /// Output format (text/dot/png/svg/html/mermaid/graphml/gexf/cytoscape/
+    /// json/sarif/cyclonedx-json/cyclonedx-xml/spdx-json/spdx-tag-value)
+    /// [default: text]
     #[arg(short, long)]
-    path: String,
+    format: Option<String>,
+
+    /// Levels of the text tree to show below each root
+    #[arg(long, value_name = "N")]
+    tree_depth: Option<usize>,
+
+    /// Show the text tree inverted: each package lists what depends on it
+    #[arg(long)]
+    invert: bool,
+
+    /// Only graph what this package or project depends on
+    #[arg(long, value_name = "PACKAGE")]
+    focus: Option<String>,
+
+    /// Only graph the packages and projects that lead to this package
+    #[arg(long, value_name = "PACKAGE")]
+    reaching: Option<String>,
+
+    /// Only graph packages within N steps of --focus/--reaching, or of the projects
+    #[arg(long, value_name = "N")]
+    graph_depth: Option<usize>,
+
+    /// Only graph packages in this category (repeatable)
+    #[arg(long = "category", value_name = "NAME")]
+    categories: Vec<String>,
 
-    /// Output format (text/dot/png)
-    #[arg(short, long, default_value = "text")]
-    format: String,
+    /// Only follow requirements with this scope, e.g. main, dev, optional (repeatable)
+    #[arg(long = "scope", value_name = "SCOPE")]
+    scopes: Vec<String>,
+
+    /// Hide packages without dependencies of their own
+    #[arg(long)]
+    hide_leaves: bool,
+
+    /// Draw each category as a single node
+    #[arg(long)]
+    collapse_clusters: bool,
+
+    /// Categorize packages no configured category matches by their PyPI
+    /// trove classifiers (needs network access)
+    #[arg(long)]
+    trove_classifiers: bool,
 
     /// Check for security vulnerabilities
     #[arg(short, long)]
@@ -29,6 +146,132 @@ struct Args {
     /// Output file for graph
     #[arg(short, long)]
     output: Option<String>,
+
+    /// Baseline file; only findings missing from it are reported as new
+    #[arg(short, long)]
+    baseline: Option<String>,
+
+    /// Record the current findings into the baseline file instead of comparing
+    #[arg(long)]
+    update_baseline: bool,
+
+    /// Fail if any dependency file cannot be read or parsed
+    #[arg(long)]
+    strict: bool,
+
+    /// Configuration file (default: .dependency-analyzer.toml or
+    /// [tool.dependency-analyzer] in pyproject.toml under --path)
+    #[arg(short, long)]
+    config: Option<String>,
+
+    /// Only scan dependency files matching this glob (repeatable); replaces
+    /// the include list of the config file
+    #[arg(long)]
+    include: Vec<String>,
+
+    /// Skip dependency files matching this glob (repeatable); added to the
+    /// exclude list of the config file
+    #[arg(long)]
+    exclude: Vec<String>,
+
+    /// Maximum directory depth to descend into below --path
+    #[arg(long)]
+    max_depth: Option<usize>,
+
+    /// Do not honor .gitignore and .ignore files
+    #[arg(long)]
+    no_ignore: bool,
+
+    /// Also scan virtualenvs, caches, build output and vendored directories
+    #[arg(long)]
+    no_default_excludes: bool,
+
+    /// Do not follow symbolic links
+    #[arg(long)]
+    no_follow_links: bool,
+
+    /// Also check conflicts across projects linked by path or editable requirements
+    #[arg(long)]
+    cross_project: bool,
+
+    /// Target Python version for environment markers (repeatable)
+    #[arg(long = "python-version")]
+    python_versions: Vec<String>,
+
+    /// Do not report findings below this severity
+    #[arg(long, value_enum)]
+    min_severity: Option<Severity>,
+
+    /// Exit with an error if a reported finding is at or above this severity
+    #[arg(long, value_enum)]
+    fail_on: Option<Severity>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the effective configuration after merging the config file and CLI flags
+    Config,
+    /// Report packages required with different versions in different projects
+    Consistency {
+        /// Report format (text/markdown/json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Also list packages that are declared identically everywhere
+        #[arg(long)]
+        all: bool,
+    },
+    /// Report dependency counts, depth, fan-in, blast radius and
+    /// articulation points of the dependency graph
+    Metrics {
+        /// Report format (text/markdown/json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Number of packages listed in each ranking
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+    /// Compare two analyses: scanned directories, JSON reports written by
+    /// --format json, or git revisions of the repository at --path
+    Diff {
+        /// Directory, report file or git revision to compare from
+        old: String,
+
+        /// Directory, report file or git revision to compare to
+        new: String,
+
+        /// Report format (text/markdown/json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+    /// Show when each requirement was added, changed and removed across
+    /// the git history of the manifests under --path
+    History {
+        /// Report format (text/csv/json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Only show this package (repeatable)
+        #[arg(long = "package", value_name = "NAME")]
+        packages: Vec<String>,
+    },
+    /// Show every path from a project's requirements to a package
+    Why {
+        /// Package to explain
+        package: String,
+
+        /// Report format (text/json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Stop after listing this many paths
+        #[arg(long, default_value_t = 100)]
+        max_paths: usize,
+    },
 }
 
 #[derive(Debug)]
@@ -38,126 +281,856 @@ struct Dependency {
     version_req: Option<VersionReq>,
     resolved_version: Option<Version>,
     source_file: String,
+    span: SourceSpan,
+    /// Id of the project the declaring manifest belongs to
+    project: String,
+    /// Target directory of a path or editable requirement
+    local_path: Option<PathBuf>,
+    /// `main`, `dev`, `optional:<extra>` or `group:<name>`
+    scope: String,
+}
+
+impl Dependency {
+    fn location(&self) -> Location {
+        Location {
+            file: self.source_file.clone(),
+            span: self.span,
+        }
+    }
+
+    /// Whether both requirements end up in the same environment. Main
+    /// requirements are installed with every extra and group, but different
+    /// extras or groups are not necessarily installed together.
+    fn installed_with(&self, other: &Dependency) -> bool {
+        self.scope == other.scope || self.scope == "main" || other.scope == "main"
+    }
+
+    /// Package ecosystem for purls: conda for environment.yml, PyPI otherwise.
+    fn ecosystem(&self) -> &'static str {
+        if self.source_file.ends_with("environment.yml") {
+            "conda"
+        } else {
+            "pypi"
+        }
+    }
+
+    /// The exact version of an `==1.2.3` (or conda `=1.2.3`) requirement.
+    fn pinned_version(&self) -> Option<String> {
+        let spec = tree::strip_name(&self.name, &self.version_spec);
+        let version = spec.trim_start_matches('=').trim();
+        let exact = spec.starts_with('=')
+            && !version.is_empty()
+            && !version.contains([',', '*', ' ', '<', '>', '!', '~', ';']);
+        exact.then(|| version.to_string())
+    }
+}
+
+/// 1-based line/column range of a requirement inside its manifest. Columns
+/// count characters, and the end is exclusive. A zero line means unknown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct SourceSpan {
+    line: usize,
+    column: usize,
+    end_line: usize,
+    end_column: usize,
+}
+
+impl SourceSpan {
+    /// Build a span from byte offsets into `content`.
+    fn from_offsets(content: &str, start: usize, end: usize) -> Self {
+        let (line, column) = line_column(content, start);
+        let (end_line, end_column) = line_column(content, end);
+        Self {
+            line,
+            column,
+            end_line,
+            end_column,
+        }
+    }
+
+    fn is_known(&self) -> bool {
+        self.line > 0
+    }
+}
+
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+/// Read a Python release such as `2.0` or `1.26.4` as a semver version.
+fn python_to_semver(version: &str) -> Option<Version> {
+    let release = specifier::PyVersion::parse(version)?.to_string();
+    let mut parts: Vec<&str> = release.split('.').collect();
+    parts.resize(3.max(parts.len()), "0");
+    Version::parse(&parts[..3].join(".")).ok()
+}
+
+/// The distribution name at the start of a requirement string.
+fn requirement_name(requirement: &str) -> &str {
+    requirement
+        .split(['=', '>', '<', '~', '!', ';', '[', '@', ' '])
+        .next()
+        .unwrap_or(requirement)
+        .trim()
+}
+
+/// Scope for a named dependency group; `dev` groups are plain `dev`.
+fn group_scope(group: &str) -> String {
+    if group == "dev" {
+        "dev".to_string()
+    } else {
+        format!("group:{}", group)
+    }
+}
+
+static MARKER_CLAUSE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"^\(?\s*(python_version|python_full_version)\s*(<=|>=|==|!=|~=|<|>)\s*['"]([^'"]+)['"]\s*\)?$"#,
+    )
+    .expect("valid marker regex")
+});
+
+/// Evaluate the `python_version` / `python_full_version` comparisons of an
+/// environment marker against the target versions. Other marker variables
+/// are assumed to match, and with no targets every marker matches.
+fn marker_matches(marker: &str, python_versions: &[String]) -> bool {
+    if python_versions.is_empty() {
+        return true;
+    }
+
+    python_versions.iter().any(|target| {
+        let Some(target) = specifier::PyVersion::parse(target) else {
+            return true;
+        };
+        marker.split(" or ").any(|alternative| {
+            alternative.split(" and ").all(|clause| {
+                let Some(caps) = MARKER_CLAUSE.captures(clause.trim()) else {
+                    return true;
+                };
+                // Same rules as requirement specifiers, so `~=3.8` stops
+                // below 4 and `==3.*` covers every 3.x
+                specifier::VersionRange::parse(&format!("{}{}", &caps[2], &caps[3]))
+                    .is_none_or(|range| range.contains(&target))
+            })
+        })
+    })
+}
+
+static TOML_TABLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^[ \t]*\[([^\[\]\n]+)\][ \t\r]*(?:#.*)?$").expect("valid table regex")
+});
+
+/// Byte offset just past the `[name]` table header, if present.
+fn find_toml_table(content: &str, name: &str) -> Option<usize> {
+    TOML_TABLE
+        .captures_iter(content)
+        .find(|caps| caps[1].trim() == name)
+        .and_then(|caps| caps.get(1))
+        .map(|name| name.end() + 1)
+}
+
+static TOML_HEADER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^[ \t]*\[\[?[A-Za-z0-9_.\- ]+\]\]?[ \t]*(?:#.*)?$")
+        .expect("valid table header regex")
+});
+
+static TOML_KEY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?m)^[ \t]*((?:"([^"\n]*)"|'([^'\n]*)'|([A-Za-z0-9_\-]+))[ \t]*=[^\n#]*)"#)
+        .expect("valid key regex")
+});
+
+/// Byte range of the `key = value` statement for `key` in the `[table]`
+/// section, which ends at the next table header.
+fn find_toml_key(content: &str, table: &str, key: &str) -> Option<(usize, usize)> {
+    let start = find_toml_table(content, table)?;
+    let end = TOML_HEADER
+        .find(&content[start..])
+        .map_or(content.len(), |m| start + m.start());
+    let m = TOML_KEY
+        .captures_iter(&content[start..end])
+        .find(|caps| caps.get(2).or(caps.get(3)).or(caps.get(4)).map(|k| k.as_str()) == Some(key))?
+        .get(1)?;
+    Some((start + m.start(), start + m.start() + m.as_str().trim_end().len()))
+}
+
+static YAML_ITEM: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?m)^[ \t]*-[ \t]+(?:"([^"\n]*)"|'([^'\n]*)'|([^\s"'#][^\n#]*?))[ \t\r]*(?:#.*)?$"#,
+    )
+    .expect("valid list item regex")
+});
+
+/// Byte range of the YAML list item `- value`, optionally quoted, searching
+/// from `from`.
+fn find_yaml_item(content: &str, value: &str, from: usize) -> Option<(usize, usize)> {
+    let m = YAML_ITEM
+        .captures_iter(&content[from..])
+        .filter_map(|caps| caps.get(1).or(caps.get(2)).or(caps.get(3)))
+        .find(|m| m.as_str() == value)?;
+    Some((from + m.start(), from + m.end()))
+}
+
+/// Byte range of the contents of a quoted string equal to `value`, searching from `from`.
+fn find_quoted(content: &str, value: &str, from: usize) -> Option<(usize, usize)> {
+    ['"', '\''].iter().find_map(|quote| {
+        let needle = format!("{}{}{}", quote, value, quote);
+        content[from..]
+            .find(&needle)
+            .map(|idx| (from + idx + 1, from + idx + 1 + value.len()))
+    })
+}
+
+/// Where in a manifest a finding was detected.
+#[derive(Debug, Clone)]
+struct Location {
+    file: String,
+    span: SourceSpan,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.span.is_known() {
+            write!(f, "{}:{}:{}", self.file, self.span.line, self.span.column)
+        } else {
+            write!(f, "{}", self.file)
+        }
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A check that can produce findings.
+struct Rule {
+    id: &'static str,
+    /// PascalCase name, as used by SARIF consumers
+    name: &'static str,
+    description: &'static str,
+    severity: Severity,
+}
+
+/// Every rule `check_conflicts` can report, in a stable order.
+const RULES: &[Rule] = &[
+    Rule {
+        id: "multiple-versions",
+        name: "MultipleVersions",
+        description: "The same package is required with different version specifiers",
+        severity: Severity::Error,
+    },
+    Rule {
+        id: "known-conflict",
+        name: "KnownConflict",
+        description: "Two packages listed in the known-conflict table are used together",
+        severity: Severity::Warning,
+    },
+    Rule {
+        id: "outdated-version",
+        name: "OutdatedVersion",
+        description: "A version range that is too old for current ML frameworks",
+        severity: Severity::Warning,
+    },
+    Rule {
+        id: "incompatible-requirement",
+        name: "IncompatibleRequirement",
+        description: "A package requires a newer version of another package than the one allowed",
+        severity: Severity::Error,
+    },
+    Rule {
+        id: "mixed-backends",
+        name: "MixedBackends",
+        description: "transformers is used with both tensorflow and torch",
+        severity: Severity::Info,
+    },
+    Rule {
+        id: "dependency-cycle",
+        name: "DependencyCycle",
+        description: "Packages depend on each other in a cycle",
+        severity: Severity::Warning,
+    },
+];
+
+/// A manifest that could not be read or parsed during the scan.
+#[derive(Debug, Clone)]
+struct Diagnostic {
+    file: String,
+    span: SourceSpan,
+    message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location = Location {
+            file: self.file.clone(),
+            span: self.span,
+        };
+        write!(f, "{}: {}", location, self.message)
+    }
+}
+
+/// Syntax error in a manifest, with the position reported by the parser.
+#[derive(Debug)]
+struct ParseError {
+    span: SourceSpan,
+    message: String,
+}
+
+impl ParseError {
+    fn from_toml(content: &str, error: toml::de::Error) -> Self {
+        let span = error
+            .span()
+            .map(|range| SourceSpan::from_offsets(content, range.start, range.end))
+            .unwrap_or_default();
+        Self {
+            span,
+            message: error.message().trim().replace('\n', ": "),
+        }
+    }
+
+    fn from_yaml(content: &str, error: serde_yaml::Error) -> Self {
+        let span = error
+            .location()
+            .map(|location| SourceSpan::from_offsets(content, location.index(), location.index()))
+            .unwrap_or_default();
+        Self {
+            span,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A single problem reported by `check_conflicts`.
+#[derive(Debug, Clone)]
+struct Finding {
+    rule: &'static str,
+    severity: Severity,
+    message: String,
+    packages: Vec<String>,
+    locations: Vec<Location>,
+}
+
+impl Finding {
+    fn new(
+        rule: &'static str,
+        severity: Severity,
+        message: String,
+        packages: Vec<String>,
+        locations: Vec<Location>,
+    ) -> Self {
+        Self {
+            rule,
+            severity,
+            message,
+            packages,
+            locations,
+        }
+    }
+
+    /// Manifest paths relative to `root`, sorted and deduplicated.
+    fn relative_files(&self, root: &Path) -> Vec<String> {
+        let mut files: Vec<String> = self
+            .locations
+            .iter()
+            .map(|l| relative_path(&l.file, root))
+            .collect();
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    /// Stable identifier built from the rule, the packages involved and the
+    /// manifests they come from. Versions and message text are deliberately
+    /// left out so that bumping a pin does not turn an old finding into a new one.
+    fn fingerprint(&self, root: &Path) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.rule.as_bytes());
+        for package in &self.packages {
+            hasher.update([0]);
+            hasher.update(package.as_bytes());
+        }
+        for file in self.relative_files(root) {
+            hasher.update([1]);
+            hasher.update(file.as_bytes());
+        }
+        hasher.finalize()[..8]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Render `file` relative to `root` with forward slashes, falling back to the
+/// path as given when it lies outside the root.
+fn relative_path(file: &str, root: &Path) -> String {
+    let path = Path::new(file);
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative
+        .components()
+        .map(|c| match c {
+            // Joined with "/" below, so an absolute path keeps one leading slash
+            std::path::Component::RootDir => "".into(),
+            c => c.as_os_str().to_string_lossy(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Strongly connected components of the dependency graph that form cycles.
+struct CycleMembership {
+    /// Component of each node by index, for components of several nodes
+    component: Vec<Option<usize>>,
+    /// Packages depending on themselves
+    self_loops: HashSet<NodeIndex>,
+}
+
+impl CycleMembership {
+    /// Whether a package is part of a dependency cycle.
+    fn contains(&self, node: NodeIndex) -> bool {
+        self.component[node.index()].is_some() || self.self_loops.contains(&node)
+    }
+
+    /// Whether an edge lies on a cycle: its target leads back to its source.
+    fn has_edge(&self, from: NodeIndex, to: NodeIndex) -> bool {
+        let component = self.component[from.index()];
+        from == to || (component.is_some() && component == self.component[to.index()])
+    }
 }
 
 struct DependencyAnalyzer {
-    dependencies: HashMap<String, Vec<Dependency>>,
+    root: PathBuf,
+    /// Ordered by name so every output is stable across runs
+    dependencies: BTreeMap<String, Vec<Dependency>>,
     graph: DiGraph<String, ()>,
     node_map: HashMap<String, NodeIndex>,
-    known_conflicts: HashMap<String, HashSet<String>>,
+    known_conflicts: BTreeMap<String, BTreeSet<String>>,
+    diagnostics: Vec<Diagnostic>,
+    config: Config,
+    projects: Vec<Project>,
+    /// (dependent, dependency) project ids linked by path requirements
+    project_links: BTreeSet<(String, String)>,
+    workspaces: Vec<Workspace>,
+    /// Packages provided by projects inside the scanned tree
+    local_packages: BTreeSet<String>,
+    /// Project root of each lockfile and the packages it pins
+    lockfiles: Vec<(PathBuf, BTreeMap<String, LockedPackage>)>,
+    /// Cluster node each package was folded into by `--collapse-clusters`
+    collapsed: BTreeMap<String, String>,
+    /// Compiled package patterns of each configured category
+    category_matchers: Vec<GlobSet>,
+    /// Where manifests and lockfiles are read from
+    files: FileSystem,
 }
 
 impl DependencyAnalyzer {
-    fn new() -> Self {
-        let mut known_conflicts = HashMap::new();
+    fn new(config: Config) -> Self {
+        let mut known_conflicts = BTreeMap::new();
 
         // Known conflicting package combinations
         known_conflicts.insert("tensorflow".to_string(), {
-            let mut s = HashSet::new();
+            let mut s = BTreeSet::new();
             s.insert("torch".to_string()); // Any torch version conflicts
             s.insert("jax".to_string());   // Potential memory conflicts
             s
         });
 
         known_conflicts.insert("torch".to_string(), {
-            let mut s = HashSet::new();
+            let mut s = BTreeSet::new();
             s.insert("tensorflow".to_string());
             s.insert("jax".to_string());
             s
         });
 
         known_conflicts.insert("numpy".to_string(), {
-            let mut s = HashSet::new();
+            let mut s = BTreeSet::new();
             s.insert("pandas<1.0.0".to_string()); // Old pandas versions
             s
         });
 
         Self {
-            dependencies: HashMap::new(),
+            root: PathBuf::new(),
+            dependencies: BTreeMap::new(),
             graph: DiGraph::new(),
             node_map: HashMap::new(),
             known_conflicts,
+            diagnostics: Vec::new(),
+            config,
+            projects: Vec::new(),
+            project_links: BTreeSet::new(),
+            workspaces: Vec::new(),
+            local_packages: BTreeSet::new(),
+            lockfiles: Vec::new(),
+            collapsed: BTreeMap::new(),
+            category_matchers: Vec::new(),
+            files: FileSystem::Disk,
+        }
+    }
+
+    /// Compile the package patterns of the configured categories.
+    fn load_categories(&mut self) -> Result<()> {
+        self.category_matchers = self
+            .config
+            .categories
+            .iter()
+            .map(|category| category.matcher())
+            .collect::<Result<_>>()?;
+        Ok(())
+    }
+
+    /// Add the rules read from the config's rules files to the
+    /// known-conflict table.
+    fn add_conflict_rules(&mut self, rules: Vec<ConflictRule>) {
+        for rule in rules {
+            self.known_conflicts
+                .entry(lockfile::normalize_name(&rule.package))
+                .or_default()
+                .extend(rule.conflicts_with.iter().map(|p| lockfile::normalize_name(p)));
         }
     }
 
     fn scan_project(&mut self, path: &Path) -> Result<()> {
-        println!("{}", "Scanning project for dependency files...".cyan());
+        status!("{}", "Scanning project for dependency files...".cyan());
+        self.root = path.to_path_buf();
 
-        for entry in WalkDir::new(path)
-            .follow_links(true)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            let file_name = entry.file_name().to_string_lossy();
-            match file_name.as_ref() {
-                "requirements.txt" => self.parse_requirements(&entry.path())?,
-                "setup.py" => self.parse_setup_py(&entry.path())?,
-                "Pipfile" => self.parse_pipfile(&entry.path())?,
-                "pyproject.toml" => self.parse_pyproject_toml(&entry.path())?,
-                "environment.yml" => self.parse_conda_yml(&entry.path())?,
+        let mut manifests = Vec::new();
+        for event in walk::find_manifests(path, &self.config, &self.files)? {
+            match event {
+                WalkEvent::Manifest(manifest) => manifests.push(manifest),
+                WalkEvent::Error { path, message } => {
+                    self.diagnostics.push(Diagnostic {
+                        file: path.display().to_string(),
+                        span: SourceSpan::default(),
+                        message,
+                    });
+                }
+            }
+        }
+
+        self.projects = project::detect_projects(path, &manifests, &self.files);
+        if self.projects.len() > 1 {
+            status!("Detected {} projects:", self.projects.len());
+            for project in &self.projects {
+                status!("  - {} ({})", project.name, project.id);
+            }
+        }
+
+        for manifest in &manifests {
+            let manifest = manifest.as_path();
+
+            let file_name = manifest
+                .file_name()
+                .map(|n| n.to_string_lossy())
+                .unwrap_or_default();
+            let result = match file_name.as_ref() {
+                "requirements.txt" => self.parse_requirements(manifest),
+                "setup.py" => self.parse_setup_py(manifest),
+                "setup.cfg" => self.parse_setup_cfg(manifest),
+                "Pipfile" => self.parse_pipfile(manifest),
+                "pyproject.toml" => self.parse_pyproject_toml(manifest),
+                "environment.yml" => self.parse_conda_yml(manifest),
                 _ => continue,
+            };
+            if let Err(e) = result {
+                let (span, message) = match e.downcast_ref::<ParseError>() {
+                    Some(parse_error) => (parse_error.span, parse_error.message.clone()),
+                    None => (SourceSpan::default(), format!("{:#}", e)),
+                };
+                self.diagnostics.push(Diagnostic {
+                    file: manifest.display().to_string(),
+                    span,
+                    message,
+                });
             }
         }
+
+        self.link_projects();
+        self.load_lockfiles();
+        for workspace in &self.workspaces {
+            let members: Vec<&str> = workspace
+                .member_projects(&self.projects)
+                .iter()
+                .map(|p| p.name.as_str())
+                .collect();
+            status!(
+                "Detected {} workspace at {}: {}",
+                workspace.tool,
+                self.project_id(&workspace.root),
+                members.join(", ")
+            );
+        }
+
+        if !self.diagnostics.is_empty() {
+            status!("\n{}", "Scan Diagnostics:".yellow());
+            for diagnostic in &self.diagnostics {
+                status!("  - {}", diagnostic);
+            }
+            let skipped: HashSet<&str> = self.diagnostics.iter().map(|d| d.file.as_str()).collect();
+            status!(
+                "{}",
+                format!("Skipped {} file(s) that could not be read or parsed", skipped.len()).yellow()
+            );
+        }
         Ok(())
     }
 
+    /// Record which projects depend on each other through path requirements
+    /// or workspace membership, and connect their nodes in the graph.
+    fn link_projects(&mut self) {
+        // Inside a workspace, a plain requirement on a member resolves to it
+        for workspace in &self.workspaces {
+            let members = workspace.member_projects(&self.projects);
+            let ids: HashSet<&str> = members.iter().map(|p| p.id.as_str()).collect();
+            for (name, deps) in self.dependencies.iter_mut() {
+                let wanted = lockfile::normalize_name(name);
+                let Some(member) = members
+                    .iter()
+                    .find(|p| lockfile::normalize_name(&p.name) == wanted)
+                else {
+                    continue;
+                };
+                for dep in deps.iter_mut() {
+                    if dep.local_path.is_none() && ids.contains(dep.project.as_str()) {
+                        dep.local_path = Some(member.root.clone());
+                    }
+                }
+            }
+        }
+
+        for dep in self.dependencies.values().flatten() {
+            let Some(local_path) = &dep.local_path else {
+                continue;
+            };
+            self.local_packages.insert(dep.name.clone());
+            if let Some(target) = self.projects.iter().find(|p| &p.root == local_path) {
+                if target.id != dep.project {
+                    self.project_links
+                        .insert((dep.project.clone(), target.id.clone()));
+                }
+            }
+        }
+
+        let names: HashMap<String, String> = self
+            .projects
+            .iter()
+            .map(|p| (p.id.clone(), p.name.clone()))
+            .collect();
+        for (from, to) in self.project_links.clone() {
+            if let (Some(from), Some(to)) = (names.get(&from), names.get(&to)) {
+                self.local_packages.insert(from.clone());
+                let from = self.ensure_node(from);
+                let to = self.ensure_node(to);
+                if self.graph.find_edge(from, to).is_none() {
+                    self.graph.add_edge(from, to, ());
+                }
+            }
+        }
+    }
+
+    /// Read the lockfiles in every project root and record the versions
+    /// they pin on the matching dependencies.
+    fn load_lockfiles(&mut self) {
+        for project in &self.projects {
+            for name in lockfile::LOCKFILE_NAMES {
+                let path = project.root.join(name);
+                if !self.files.is_file(&path) {
+                    continue;
+                }
+                match lockfile::load(&path, &self.files) {
+                    Ok(packages) => self.lockfiles.push((project.root.clone(), packages)),
+                    Err(e) => self.diagnostics.push(Diagnostic {
+                        file: path.display().to_string(),
+                        span: SourceSpan::default(),
+                        message: format!("{:#}", e),
+                    }),
+                }
+            }
+        }
+        let mut resolved = Vec::new();
+        for (name, deps) in &self.dependencies {
+            for (i, dep) in deps.iter().enumerate() {
+                if let Some(locked) = self.locked_package(dep) {
+                    resolved.push((name.clone(), i, python_to_semver(&locked.version)));
+                }
+            }
+        }
+        for (name, i, version) in resolved {
+            if let Some(dep) = self.dependencies.get_mut(&name).and_then(|d| d.get_mut(i)) {
+                dep.resolved_version = version;
+            }
+        }
+    }
+
+    /// The lock entry for `dep` from its project's lockfile, or from the
+    /// lockfile of a workspace the project is a member of.
+    fn locked_package(&self, dep: &Dependency) -> Option<&LockedPackage> {
+        if dep.ecosystem() != "pypi" || dep.local_path.is_some() {
+            return None;
+        }
+        let project = self.projects.iter().find(|p| p.id == dep.project)?;
+        let mut roots = vec![&project.root];
+        for workspace in &self.workspaces {
+            if workspace
+                .member_projects(&self.projects)
+                .iter()
+                .any(|p| p.id == project.id)
+            {
+                roots.push(&workspace.root);
+            }
+        }
+        let name = lockfile::normalize_name(&dep.name);
+        roots.into_iter().find_map(|root| {
+            self.lockfiles
+                .iter()
+                .filter(|(lock_root, _)| lock_root == root)
+                .find_map(|(_, packages)| packages.get(&name))
+        })
+    }
+
+    fn ensure_node(&mut self, name: &str) -> NodeIndex {
+        if let Some(&node) = self.node_map.get(name) {
+            return node;
+        }
+        let node = self.graph.add_node(name.to_string());
+        self.node_map.insert(name.to_string(), node);
+        node
+    }
+
+    /// Add the components of a CycloneDX or SPDX document as the
+    /// dependencies of a project named after the software it describes.
+    fn import_sbom(&mut self, path: &Path) -> Result<()> {
+        status!("Importing SBOM: {}", path.display());
+        let sbom = sbom_import::load(path)?;
+        let id = format!("sbom:{}", relative_path(&path.display().to_string(), &self.root));
+        self.projects.push(Project {
+            id: id.clone(),
+            name: sbom.name,
+            root: path.to_path_buf(),
+            version: sbom.version,
+            license: None,
+        });
+
+        for component in sbom.components {
+            let version_spec = match &component.version {
+                Some(version) => format!("{}=={}", component.name, version),
+                None => component.name.clone(),
+            };
+            let dep = Dependency {
+                name: component.name,
+                version_req: self.parse_version_spec(&version_spec),
+                resolved_version: component.version.as_deref().and_then(python_to_semver),
+                version_spec,
+                source_file: path.display().to_string(),
+                span: component.span,
+                project: id.clone(),
+                local_path: None,
+                scope: component.scope.to_string(),
+            };
+            self.add_dependency(dep);
+        }
+        for (from, to) in sbom.edges {
+            let from = self.ensure_node(&self.canonical_name(&from));
+            let to = self.ensure_node(&self.canonical_name(&to));
+            if self.graph.find_edge(from, to).is_none() {
+                self.graph.add_edge(from, to, ());
+            }
+        }
+        Ok(())
+    }
+
+    /// Id of the project owning `path`.
+    fn project_id(&self, path: &Path) -> String {
+        project::owning_project(&self.projects, path)
+            .map(|p| p.id.clone())
+            .unwrap_or_else(|| ".".to_string())
+    }
+
     fn parse_version_spec(&self, spec: &str) -> Option<VersionReq> {
         // Convert pip-style version specs to semver-style
         let spec = spec
-            .replace(">=", ">=")
-            .replace("<=", "<=")
             .replace("==", "=")
             .replace("~=", "~");
         VersionReq::parse(&spec).ok()
     }
 
     fn parse_requirements(&mut self, path: &Path) -> Result<()> {
-        println!("Parsing requirements.txt: {}", path.display());
-        let content = fs::read_to_string(path)?;
+        status!("Parsing requirements.txt: {}", path.display());
+        let content = self.files.read_to_string(path)?;
+
+        let mut offset = 0;
+        for raw_line in content.split_inclusive('\n') {
+            let line_start = offset;
+            offset += raw_line.len();
 
-        for line in content.lines() {
-            let line = line.trim();
+            // Drop inline comments so they don't end up in the version spec
+            let without_comment = match raw_line.find(" #").or_else(|| raw_line.find("\t#")) {
+                Some(idx) => &raw_line[..idx],
+                None => raw_line,
+            };
+            let line = without_comment.trim();
             if line.is_empty() || line.starts_with('#') {
                 continue;
             }
-
-            if let Some(pkg_name) = line.split(&['=', '>', '<', '~', '!'][..]).next() {
-                let dep = Dependency {
-                    name: pkg_name.trim().to_string(),
-                    version_spec: line.to_string(),
-                    version_req: self.parse_version_spec(line),
-                    resolved_version: None,
-                    source_file: path.display().to_string(),
-                };
-                self.add_dependency(dep);
+            // Options such as -r, -c or --index-url are not requirements
+            if line.starts_with('-') && !line.starts_with("-e") && !line.starts_with("--editable") {
+                continue;
             }
+            let start = line_start + (without_comment.len() - without_comment.trim_start().len());
+            let span = SourceSpan::from_offsets(&content, start, start + line.len());
+            self.add_requirement(line, path, span, "main");
         }
         Ok(())
     }
 
     fn parse_setup_py(&mut self, path: &Path) -> Result<()> {
-        println!("Parsing setup.py: {}", path.display());
-        let content = fs::read_to_string(path)?;
+        status!("Parsing setup.py: {}", path.display());
+        let content = self.files.read_to_string(path)?;
         let install_requires_re = Regex::new(r"(?s)install_requires\s*=\s*\[(.*?)\]")?;
+        // Comments are matched (and ignored) so quotes inside them are not picked up
+        let item_re = Regex::new(r#"#[^\n]*|'([^'\n]*)'|"([^"\n]*)""#)?;
 
         if let Some(caps) = install_requires_re.captures(&content) {
             if let Some(requires) = caps.get(1) {
-                for req in requires.as_str().split(',') {
-                    let req = req.trim().trim_matches('\'').trim_matches('"');
+                for item in item_re.captures_iter(requires.as_str()) {
+                    let Some(req_match) = item.get(1).or_else(|| item.get(2)) else {
+                        continue;
+                    };
+                    let req = req_match.as_str().trim();
                     if !req.is_empty() {
-                        let dep = Dependency {
-                            name: req
-                                .split(&['=', '>', '<', '~', '!'][..])
-                                .next()
-                                .unwrap_or(req)
-                                .trim()
-                                .to_string(),
-                            version_spec: req.to_string(),
-                            version_req: self.parse_version_spec(req),
-                            resolved_version: None,
-                            source_file: path.display().to_string(),
-                        };
-                        self.add_dependency(dep);
+                        let start = requires.start() + req_match.start();
+                        let span = SourceSpan::from_offsets(&content, start, start + req_match.len());
+                        self.add_requirement(req, path, span, "main");
                     }
                 }
             }
@@ -165,90 +1138,448 @@ impl DependencyAnalyzer {
         Ok(())
     }
 
+    fn parse_setup_cfg(&mut self, path: &Path) -> Result<()> {
+        status!("Parsing setup.cfg: {}", path.display());
+        let content = self.files.read_to_string(path)?;
+
+        // install_requires is a multi-line value under [options]; continuation
+        // lines are indented
+        let mut in_options = false;
+        let mut in_requires = false;
+        let mut offset = 0;
+        for raw_line in content.split_inclusive('\n') {
+            let line_start = offset;
+            offset += raw_line.len();
+            let line = raw_line.trim_end();
+            let trimmed = line.trim();
+
+            if trimmed.starts_with('[') {
+                in_options = trimmed == "[options]";
+                in_requires = false;
+                continue;
+            }
+            if !in_options || trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with(';') {
+                continue;
+            }
+
+            let is_continuation = line.starts_with(char::is_whitespace);
+            let value = if !is_continuation {
+                match trimmed.split_once('=') {
+                    Some((key, value)) if key.trim() == "install_requires" => {
+                        in_requires = true;
+                        value.trim()
+                    }
+                    _ => {
+                        in_requires = false;
+                        continue;
+                    }
+                }
+            } else if in_requires {
+                trimmed
+            } else {
+                continue;
+            };
+
+            if !value.is_empty() {
+                let start = line_start + line.find(value).unwrap_or(0);
+                let span = SourceSpan::from_offsets(&content, start, start + value.len());
+                self.add_requirement(value, path, span, "main");
+            }
+        }
+        Ok(())
+    }
+
     fn parse_pipfile(&mut self, path: &Path) -> Result<()> {
-        println!("Parsing Pipfile: {}", path.display());
-        let content = fs::read_to_string(path)?;
-        let pipfile: toml::Value = toml::from_str(&content)?;
+        status!("Parsing Pipfile: {}", path.display());
+        let content = self.files.read_to_string(path)?;
+        let pipfile: toml::Value =
+            toml::from_str(&content).map_err(|e| ParseError::from_toml(&content, e))?;
 
         if let Some(packages) = pipfile.get("packages").and_then(|p| p.as_table()) {
             for (name, version) in packages {
-                let version_spec = match version {
-                    toml::Value::String(v) => v.clone(),
-                    _ => "*".to_string(),
-                };
-                let dep = Dependency {
-                    name: name.clone(),
-                    version_spec: version_spec.clone(),
-                    version_req: self.parse_version_spec(&version_spec),
-                    resolved_version: None,
-                    source_file: path.display().to_string(),
-                };
-                self.add_dependency(dep);
+                let span = find_toml_key(&content, "packages", name)
+                    .map(|(start, end)| SourceSpan::from_offsets(&content, start, end))
+                    .unwrap_or_default();
+                self.add_table_dependency(name, version, path, span, "main");
             }
         }
         Ok(())
     }
 
     fn parse_pyproject_toml(&mut self, path: &Path) -> Result<()> {
-        println!("Parsing pyproject.toml: {}", path.display());
-        let content = fs::read_to_string(path)?;
-        let pyproject: toml::Value = toml::from_str(&content)?;
+        status!("Parsing pyproject.toml: {}", path.display());
+        let content = self.files.read_to_string(path)?;
+        let pyproject: toml::Value =
+            toml::from_str(&content).map_err(|e| ParseError::from_toml(&content, e))?;
+        let empty = toml::value::Table::new();
+        let tool = pyproject.get("tool");
+        let uv = tool.and_then(|t| t.get("uv"));
+        let pdm = tool.and_then(|t| t.get("pdm"));
+        let poetry = tool.and_then(|t| t.get("poetry"));
+
+        let root = project::normalize_path(path.parent().unwrap_or_else(|| Path::new("")));
+        if let Some(workspace) = uv.and_then(|u| u.get("workspace")) {
+            let globs = |key: &str| -> Vec<String> {
+                workspace
+                    .get(key)
+                    .and_then(|v| v.as_array())
+                    .map(|a| a.iter().filter_map(|m| m.as_str()).map(String::from).collect())
+                    .unwrap_or_default()
+            };
+            let span = find_toml_table(&content, "tool.uv.workspace")
+                .map(|end| {
+                    let start = content[..end].rfind('[').unwrap_or(end);
+                    SourceSpan::from_offsets(&content, start, end)
+                })
+                .unwrap_or_default();
+            self.add_workspace("uv", &root, &globs("members"), &globs("exclude"), path, span);
+        }
+        // Poetry and PDM members are the in-tree projects the root installs
+        // in development mode
+        let member_glob = |target: &Path| -> Option<String> {
+            let relative = target.strip_prefix(&root).ok()?;
+            if relative.as_os_str().is_empty() {
+                return None;
+            }
+            let relative = relative_path(&relative.display().to_string(), Path::new(""));
+            Some(globset::escape(&relative))
+        };
+        let mut pdm_members = Vec::new();
+        let mut poetry_members = Vec::new();
+        // uv maps requirement names to workspace members or local paths
+        let sources = uv
+            .and_then(|u| u.get("sources"))
+            .and_then(|s| s.as_table())
+            .unwrap_or(&empty);
 
+        // PEP 621, PEP 735 and tool-specific requirement lists
+        let mut lists: Vec<(String, &toml::Value, String)> = Vec::new();
         if let Some(project) = pyproject.get("project") {
-            if let Some(dependencies) = project.get("dependencies").and_then(|d| d.as_array()) {
-                for dep in dependencies {
-                    if let Some(dep_str) = dep.as_str() {
-                        if let Some(pkg_name) = dep_str.split(&['=', '>', '<', '~', '!'][..]).next()
-                        {
-                            let dep = Dependency {
-                                name: pkg_name.trim().to_string(),
-                                version_spec: dep_str.to_string(),
-                                version_req: self.parse_version_spec(dep_str),
-                                resolved_version: None,
-                                source_file: path.display().to_string(),
-                            };
-                            self.add_dependency(dep);
+            if let Some(dependencies) = project.get("dependencies") {
+                lists.push(("project".to_string(), dependencies, "main".to_string()));
+            }
+            if let Some(extras) = project.get("optional-dependencies").and_then(|o| o.as_table()) {
+                for (extra, requirements) in extras {
+                    lists.push((
+                        "project.optional-dependencies".to_string(),
+                        requirements,
+                        format!("optional:{}", extra),
+                    ));
+                }
+            }
+        }
+        if let Some(groups) = pyproject.get("dependency-groups").and_then(|g| g.as_table()) {
+            for (group, requirements) in groups {
+                lists.push(("dependency-groups".to_string(), requirements, group_scope(group)));
+            }
+        }
+        if let Some(requirements) = uv.and_then(|u| u.get("dev-dependencies")) {
+            lists.push(("tool.uv".to_string(), requirements, "dev".to_string()));
+        }
+        if let Some(groups) = pdm
+            .and_then(|p| p.get("dev-dependencies"))
+            .and_then(|d| d.as_table())
+        {
+            for (group, requirements) in groups {
+                lists.push((
+                    "tool.pdm.dev-dependencies".to_string(),
+                    requirements,
+                    group_scope(group),
+                ));
+            }
+        }
+
+        for (table, requirements, scope) in lists {
+            let Some(requirements) = requirements.as_array() else {
+                continue;
+            };
+            let mut cursor = find_toml_table(&content, &table).unwrap_or(0);
+            for requirement in requirements.iter().filter_map(|r| r.as_str()) {
+                if table.starts_with("tool.pdm") {
+                    let member = project::parse_local_requirement(requirement, &root)
+                        .filter(|local| local.editable)
+                        .and_then(|local| member_glob(&local.path));
+                    pdm_members.extend(member);
+                }
+                let span = match find_quoted(&content, requirement, cursor) {
+                    Some((start, end)) => {
+                        cursor = end;
+                        SourceSpan::from_offsets(&content, start, end)
+                    }
+                    None => SourceSpan::default(),
+                };
+                self.add_sourced_requirement(requirement, sources, path, span, &scope);
+            }
+        }
+
+        // Poetry keeps its own name = spec tables
+        if let Some(poetry) = poetry {
+            let mut tables: Vec<(String, &toml::Value, String)> = Vec::new();
+            if let Some(dependencies) = poetry.get("dependencies") {
+                tables.push(("tool.poetry.dependencies".to_string(), dependencies, "main".to_string()));
+            }
+            if let Some(dependencies) = poetry.get("dev-dependencies") {
+                tables.push(("tool.poetry.dev-dependencies".to_string(), dependencies, "dev".to_string()));
+            }
+            if let Some(groups) = poetry.get("group").and_then(|g| g.as_table()) {
+                for (group, table) in groups {
+                    if let Some(dependencies) = table.get("dependencies") {
+                        tables.push((
+                            format!("tool.poetry.group.{}.dependencies", group),
+                            dependencies,
+                            group_scope(group),
+                        ));
+                    }
+                }
+            }
+
+            for (table, dependencies, scope) in tables {
+                let Some(dependencies) = dependencies.as_table() else {
+                    continue;
+                };
+                for (name, value) in dependencies {
+                    if name == "python" {
+                        continue;
+                    }
+                    if value.get("develop").and_then(|d| d.as_bool()) == Some(true) {
+                        let target = value.get("path").and_then(|p| p.as_str());
+                        if let Some(member) = target.and_then(|t| member_glob(&root.join(t))) {
+                            poetry_members.push(member);
                         }
                     }
+                    let span = find_toml_key(&content, &table, name)
+                        .map(|(start, end)| SourceSpan::from_offsets(&content, start, end))
+                        .unwrap_or_default();
+                    self.add_table_dependency(name, value, path, span, &scope);
                 }
             }
         }
+
+        for (tool, members) in [("pdm", pdm_members), ("poetry", poetry_members)] {
+            if !members.is_empty() {
+                self.add_workspace(tool, &root, &members, &[], path, SourceSpan::default());
+            }
+        }
         Ok(())
     }
 
+    /// Record a workspace rooted at `root`, or a diagnostic on the manifest
+    /// declaring it if its globs are invalid.
+    fn add_workspace(
+        &mut self,
+        tool: &'static str,
+        root: &Path,
+        members: &[String],
+        exclude: &[String],
+        manifest: &Path,
+        span: SourceSpan,
+    ) {
+        match Workspace::new(tool, root.to_path_buf(), members, exclude) {
+            Ok(workspace) => self.workspaces.push(workspace),
+            Err(e) => self.diagnostics.push(Diagnostic {
+                file: manifest.display().to_string(),
+                span,
+                message: format!("{:#}", e),
+            }),
+        }
+    }
+
     fn parse_conda_yml(&mut self, path: &Path) -> Result<()> {
-        println!("Parsing environment.yml: {}", path.display());
-        let content = fs::read_to_string(path)?;
-        if let Ok(yaml) = serde_yaml::from_str::<serde_yaml::Value>(&content) {
-            if let Some(dependencies) = yaml
-                .get("dependencies")
-                .and_then(|d| d.as_sequence())
-            {
-                for dep in dependencies {
-                    if let Some(dep_str) = dep.as_str() {
-                        if let Some(pkg_name) = dep_str.split(&['=', '>', '<', '~', '!'][..]).next()
-                        {
-                            let dep = Dependency {
-                                name: pkg_name.trim().to_string(),
-                                version_spec: dep_str.to_string(),
-                                version_req: self.parse_version_spec(dep_str),
-                                resolved_version: None,
-                                source_file: path.display().to_string(),
-                            };
-                            self.add_dependency(dep);
+        status!("Parsing environment.yml: {}", path.display());
+        let content = self.files.read_to_string(path)?;
+        let yaml = serde_yaml::from_str::<serde_yaml::Value>(&content)
+            .map_err(|e| ParseError::from_yaml(&content, e))?;
+        if let Some(dependencies) = yaml
+            .get("dependencies")
+            .and_then(|d| d.as_sequence())
+        {
+            let mut cursor = content.find("dependencies:").unwrap_or(0);
+            for dep in dependencies {
+                if let Some(dep_str) = dep.as_str() {
+                    let span = match find_yaml_item(&content, dep_str, cursor) {
+                        Some((start, end)) => {
+                            cursor = end;
+                            SourceSpan::from_offsets(&content, start, end)
                         }
-                    }
+                        None => SourceSpan::default(),
+                    };
+                    self.add_requirement(dep_str, path, span, "main");
                 }
             }
         }
         Ok(())
     }
 
-    fn add_dependency(&mut self, dep: Dependency) {
+    /// Add a PEP 508 style requirement string, dropping it if its environment
+    /// marker rules out every configured target Python version.
+    fn add_requirement(&mut self, requirement: &str, path: &Path, span: SourceSpan, scope: &str) {
+        let (requirement, marker) = match requirement.split_once(';') {
+            Some((requirement, marker)) => (requirement.trim(), Some(marker.trim())),
+            None => (requirement, None),
+        };
+        if let Some(marker) = marker {
+            if !marker_matches(marker, &self.config.python_versions) {
+                return;
+            }
+        }
+
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        if let Some(local) = project::parse_local_requirement(requirement, base) {
+            self.add_local_dependency(local.name, requirement, local.path, path, span, scope);
+            return;
+        }
+
+        if let Some(pkg_name) = requirement.split(&['=', '>', '<', '~', '!'][..]).next() {
+            let dep = Dependency {
+                name: pkg_name.trim().to_string(),
+                version_spec: requirement.to_string(),
+                version_req: self.parse_version_spec(requirement),
+                resolved_version: None,
+                source_file: path.display().to_string(),
+                span,
+                project: self.project_id(path),
+                local_path: None,
+                scope: scope.to_string(),
+            };
+            self.add_dependency(dep);
+        }
+    }
+
+    /// Add a requirement from a pyproject.toml list, honoring `[tool.uv.sources]`
+    /// entries that point it at a workspace member or a local path.
+    fn add_sourced_requirement(
+        &mut self,
+        requirement: &str,
+        sources: &toml::value::Table,
+        path: &Path,
+        span: SourceSpan,
+        scope: &str,
+    ) {
+        let name = requirement_name(requirement);
+        let wanted = lockfile::normalize_name(name);
+        let source = sources
+            .iter()
+            .find(|(key, _)| lockfile::normalize_name(key) == wanted)
+            .map(|(_, source)| source);
+        if let Some(source) = source {
+            let base = path.parent().unwrap_or_else(|| Path::new(""));
+            let target = if source.get("workspace").and_then(|w| w.as_bool()) == Some(true) {
+                self.projects
+                    .iter()
+                    .find(|p| lockfile::normalize_name(&p.name) == wanted)
+                    .map(|p| p.root.clone())
+            } else {
+                source
+                    .get("path")
+                    .and_then(|p| p.as_str())
+                    .map(|p| project::normalize_path(&base.join(p)))
+            };
+            if let Some(target) = target {
+                let name = Some(name.to_string());
+                self.add_local_dependency(name, requirement, target, path, span, scope);
+                return;
+            }
+        }
+        self.add_requirement(requirement, path, span, scope);
+    }
+
+    /// Add a `name = spec` entry as used by Pipfile and Poetry, where the value
+    /// is a version string or a table with `version` or `path`.
+    fn add_table_dependency(
+        &mut self,
+        name: &str,
+        value: &toml::Value,
+        path: &Path,
+        span: SourceSpan,
+        scope: &str,
+    ) {
+        if let Some(local) = value.get("path").and_then(|p| p.as_str()) {
+            let base = path.parent().unwrap_or_else(|| Path::new(""));
+            let target = project::normalize_path(&base.join(local));
+            let spec = format!("{{path = \"{}\"}}", local);
+            self.add_local_dependency(Some(name.to_string()), &spec, target, path, span, scope);
+            return;
+        }
+
+        let version_spec = match value {
+            toml::Value::String(v) => v.clone(),
+            _ => value
+                .get("version")
+                .and_then(|v| v.as_str())
+                .unwrap_or("*")
+                .to_string(),
+        };
+        let dep = Dependency {
+            name: name.to_string(),
+            version_spec: version_spec.clone(),
+            version_req: self.parse_version_spec(&version_spec),
+            resolved_version: None,
+            source_file: path.display().to_string(),
+            span,
+            project: self.project_id(path),
+            local_path: None,
+            scope: scope.to_string(),
+        };
+        self.add_dependency(dep);
+    }
+
+    /// Add a requirement on a local directory. Without an explicit name the
+    /// dependency is named after the project found there, or the directory.
+    fn add_local_dependency(
+        &mut self,
+        name: Option<String>,
+        version_spec: &str,
+        target: PathBuf,
+        path: &Path,
+        span: SourceSpan,
+        scope: &str,
+    ) {
+        let name = name
+            .or_else(|| {
+                self.projects
+                    .iter()
+                    .find(|p| p.root == target)
+                    .map(|p| p.name.clone())
+            })
+            .or_else(|| target.file_name().map(|n| n.to_string_lossy().to_string()))
+            .unwrap_or_else(|| target.display().to_string());
+        let dep = Dependency {
+            name,
+            version_spec: version_spec.to_string(),
+            version_req: None,
+            resolved_version: None,
+            source_file: path.display().to_string(),
+            span,
+            project: self.project_id(path),
+            local_path: Some(target),
+            scope: scope.to_string(),
+        };
+        self.add_dependency(dep);
+    }
+
+    /// The name a PyPI package is recorded under: the declared name of the
+    /// in-tree project it refers to, else its PEP 503 normalized name, so
+    /// every spelling of a package shares one node.
+    fn canonical_name(&self, name: &str) -> String {
+        let wanted = lockfile::normalize_name(name);
+        self.projects
+            .iter()
+            .find(|p| lockfile::normalize_name(&p.name) == wanted)
+            .map_or(wanted, |p| p.name.clone())
+    }
+
+    fn add_dependency(&mut self, mut dep: Dependency) {
+        // Requirements on an in-tree project use its declared spelling, so
+        // `My_Pkg` and `my-pkg` end up on the project's node. Conda names
+        // are not PyPI names and are kept as written
+        let canonical = self.canonical_name(&dep.name);
+        if dep.ecosystem() == "pypi" || self.projects.iter().any(|p| p.name == canonical) {
+            dep.name = canonical;
+        }
         let name = dep.name.clone();
         self.dependencies
             .entry(name.clone())
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(dep);
 
         if !self.node_map.contains_key(&name) {
@@ -285,15 +1616,160 @@ impl DependencyAnalyzer {
         }
     }
 
-    fn check_conflicts(&self) -> Vec<String> {
+    fn check_conflicts(&self) -> Vec<Finding> {
         let mut conflicts = Vec::new();
+        for scope in self.conflict_scopes() {
+            let mut dependencies: BTreeMap<&str, Vec<&Dependency>> = BTreeMap::new();
+            // Local projects are built from the tree, not resolved from an index
+            for dep in self.dependencies.values().flatten() {
+                if scope.contains(&dep.project.as_str()) && dep.local_path.is_none() {
+                    dependencies.entry(dep.name.as_str()).or_default().push(dep);
+                }
+            }
 
-        // Check for multiple version requirements
-        for (name, deps) in &self.dependencies {
+            let mut findings = self.check_scope(&dependencies);
+            if self.projects.len() > 1 {
+                let names: Vec<&str> = scope
+                    .iter()
+                    .filter_map(|id| self.projects.iter().find(|p| p.id == *id))
+                    .map(|p| p.name.as_str())
+                    .collect();
+                for finding in &mut findings {
+                    finding.message = format!("[{}] {}", names.join(", "), finding.message);
+                }
+            }
+            conflicts.extend(findings);
+        }
+        conflicts.extend(self.check_cycles());
+        conflicts
+    }
+
+    /// One finding per dependency cycle, listing its members and the edges
+    /// that close it, located at the requirements declaring those edges.
+    fn check_cycles(&self) -> Vec<Finding> {
+        let requirements = tree::RequirementGraph::build(self).requirements;
+        let edges = self.sorted_edges();
+        self.cycles()
+            .into_iter()
+            .map(|members| {
+                let names: Vec<String> = members.iter().map(|&n| self.graph[n].clone()).collect();
+                let mut links = Vec::new();
+                let mut locations = Vec::new();
+                for &(from, to) in edges.iter().filter(|(from, to)| {
+                    members.contains(from) && members.contains(to)
+                }) {
+                    let pair = (self.graph[from].clone(), self.graph[to].clone());
+                    links.push(format!("{} -> {}", pair.0, pair.1));
+                    if let Some(deps) = requirements.get(&pair) {
+                        locations.extend(deps.iter().map(|d| d.location()));
+                    }
+                }
+                Finding::new(
+                    "dependency-cycle",
+                    Severity::Warning,
+                    format!(
+                        "Dependency cycle between {}: {}",
+                        names.join(", "),
+                        links.join(", ")
+                    ),
+                    names,
+                    locations,
+                )
+            })
+            .collect()
+    }
+
+    /// Strongly connected components that form a cycle: several packages,
+    /// or one package depending on itself. Sorted by name, as are their members.
+    fn cycles(&self) -> Vec<Vec<NodeIndex>> {
+        let mut cycles: Vec<Vec<NodeIndex>> = petgraph::algo::tarjan_scc(&self.graph)
+            .into_iter()
+            .filter(|c| c.len() > 1 || self.graph.contains_edge(c[0], c[0]))
+            .map(|mut c| {
+                c.sort_by(|a, b| self.graph[*a].cmp(&self.graph[*b]));
+                c
+            })
+            .collect();
+        cycles.sort_by(|a, b| self.graph[a[0]].cmp(&self.graph[b[0]]));
+        cycles
+    }
+
+    /// Which nodes and edges lie on a cycle, for renderers to look up once
+    /// per node and edge.
+    fn cycle_membership(&self) -> CycleMembership {
+        let mut component = vec![None; self.graph.node_count()];
+        for (id, members) in petgraph::algo::tarjan_scc(&self.graph).into_iter().enumerate() {
+            if members.len() > 1 {
+                for node in members {
+                    component[node.index()] = Some(id);
+                }
+            }
+        }
+        let self_loops = self
+            .graph
+            .edge_indices()
+            .filter_map(|e| self.graph.edge_endpoints(e))
+            .filter(|(from, to)| from == to)
+            .map(|(node, _)| node)
+            .collect();
+        CycleMembership { component, self_loops }
+    }
+
+    /// Groups of project ids whose dependencies are checked against each
+    /// other: each project on its own, or with `cross-project` enabled, every
+    /// set of projects linked through path or editable requirements.
+    fn conflict_scopes(&self) -> Vec<Vec<&str>> {
+        let ids: Vec<&str> = self.projects.iter().map(|p| p.id.as_str()).collect();
+        if !self.config.cross_project {
+            return ids.into_iter().map(|id| vec![id]).collect();
+        }
+
+        // Union-find over the project links
+        let mut parent: Vec<usize> = (0..ids.len()).collect();
+        fn find(parent: &mut [usize], i: usize) -> usize {
+            let mut root = i;
+            while parent[root] != root {
+                root = parent[root];
+            }
+            parent[i] = root;
+            root
+        }
+        for (from, to) in &self.project_links {
+            let from = ids.iter().position(|id| id == from);
+            let to = ids.iter().position(|id| id == to);
+            if let (Some(from), Some(to)) = (from, to) {
+                let (a, b) = (find(&mut parent, from), find(&mut parent, to));
+                parent[a] = b;
+            }
+        }
+
+        let mut scopes: BTreeMap<usize, Vec<&str>> = BTreeMap::new();
+        for (i, id) in ids.iter().enumerate() {
+            let root = find(&mut parent, i);
+            scopes.entry(root).or_default().push(id);
+        }
+        scopes.into_values().collect()
+    }
+
+    fn check_scope(&self, dependencies: &BTreeMap<&str, Vec<&Dependency>>) -> Vec<Finding> {
+        let mut conflicts = Vec::new();
+
+        // Check for multiple version requirements among those installed together
+        for (name, deps) in dependencies {
+            let deps: Vec<&Dependency> = deps
+                .iter()
+                .enumerate()
+                .filter(|(i, d)| {
+                    deps.iter()
+                        .enumerate()
+                        .any(|(j, other)| *i != j && d.installed_with(other))
+                })
+                .map(|(_, d)| *d)
+                .collect();
             if deps.len() > 1 {
                 let versions: Vec<_> = deps
                     .iter()
-                    .map(|d| format!("{} (in {})", d.version_spec, d.source_file))
+                    .map(|d| format!("{} (in {})", d.version_spec, d.location()))
                     .collect();
 
                 // Find the highest required version
@@ -316,30 +1792,48 @@ impl DependencyAnalyzer {
                     String::new()
                 };
 
-                conflicts.push(format!(
-                    "Multiple version requirements for {}: {}{}",
-                    name,
-                    versions.join(", "),
-                    suggestion
+                conflicts.push(Finding::new(
+                    "multiple-versions",
+                    Severity::Error,
+                    format!(
+                        "Multiple version requirements for {}: {}{}",
+                        name,
+                        versions.join(", "),
+                        suggestion
+                    ),
+                    vec![name.to_string()],
+                    deps.iter().map(|d| d.location()).collect(),
                 ));
             }
         }
 
         // Check known conflicts
         for (pkg, conflict_pkgs) in &self.known_conflicts {
-            if let Some(deps) = self.dependencies.get(pkg) {
+            if let Some(deps) = dependencies.get(pkg.as_str()) {
                 for conflict_pkg in conflict_pkgs {
                     let conflict_pkg_name = conflict_pkg
                         .split('<')
                         .next()
                         .unwrap_or(conflict_pkg);
-                    if let Some(conflict_deps) = self.dependencies.get(conflict_pkg_name) {
-                        conflicts.push(format!(
-                            "Known conflict: {} {} may conflict with {} {}.\n      Suggestion: Consider using only one of these packages, or ensure they are compatible versions",
-                            pkg,
-                            deps[0].version_spec,
-                            conflict_pkg,
-                            conflict_deps[0].version_spec
+                    let pair = dependencies.get(conflict_pkg_name).and_then(|conflict_deps| {
+                        deps.iter().find_map(|dep| {
+                            let other = conflict_deps.iter().find(|c| dep.installed_with(c))?;
+                            Some((*dep, *other))
+                        })
+                    });
+                    if let Some((dep, conflict_dep)) = pair {
+                        conflicts.push(Finding::new(
+                            "known-conflict",
+                            Severity::Warning,
+                            format!(
+                                "Known conflict: {} {} may conflict with {} {}.\n      Suggestion: Consider using only one of these packages, or ensure they are compatible versions",
+                                pkg,
+                                dep.version_spec,
+                                conflict_pkg,
+                                conflict_dep.version_spec
+                            ),
+                            vec![pkg.clone(), conflict_pkg_name.to_string()],
+                            vec![dep.location(), conflict_dep.location()],
                         ));
                     }
                 }
@@ -347,40 +1841,54 @@ impl DependencyAnalyzer {
         }
 
         // Check version compatibility
-        for (name, deps) in &self.dependencies {
+        for (name, deps) in dependencies {
             if let Some(dep) = deps.first() {
                 if let Some(version_req) = &dep.version_req {
-                    match name.as_str() {
-                        "numpy" => {
-                            if version_req.to_string().contains("<1.19") {
-                                conflicts.push(format!(
+                    match *name {
+                        "numpy" if version_req.to_string().contains("<1.19") => {
+                            conflicts.push(Finding::new(
+                                "outdated-version",
+                                Severity::Warning,
+                                format!(
                                     "Warning: numpy {} might be too old for modern ML frameworks.\n      Suggestion: Use numpy>=1.19.2 for better compatibility",
                                     dep.version_spec
-                                ));
-                            }
+                                ),
+                                vec![name.to_string()],
+                                vec![dep.location()],
+                            ));
                         }
                         "tensorflow" => {
-                            if let Some(numpy_deps) = self.dependencies.get("numpy") {
-                                if let Some(numpy_dep) = numpy_deps.first() {
+                            if let Some(numpy_deps) = dependencies.get("numpy") {
+                                let numpy_dep = numpy_deps.iter().find(|n| dep.installed_with(n));
+                                if let Some(numpy_dep) = numpy_dep {
                                     if numpy_dep.version_spec.contains("<1.19") {
-                                        conflicts.push(format!(
-                                            "Potential conflict: tensorflow {} requires numpy>=1.19.2.\n      Suggestion: Upgrade numpy to version >=1.19.2",
-                                            dep.version_spec
+                                        conflicts.push(Finding::new(
+                                            "incompatible-requirement",
+                                            Severity::Error,
+                                            format!(
+                                                "Potential conflict: tensorflow {} requires numpy>=1.19.2.\n      Suggestion: Upgrade numpy to version >=1.19.2",
+                                                dep.version_spec
+                                            ),
+                                            vec![name.to_string(), "numpy".to_string()],
+                                            vec![dep.location(), numpy_dep.location()],
                                         ));
                                     }
                                 }
                             }
                         }
-                        "transformers" => {
-                            // Check if both tensorflow and torch are present
-                            if self.dependencies.contains_key("tensorflow")
-                                && self.dependencies.contains_key("torch")
-                            {
-                                conflicts.push(
-                                    "Warning: transformers is being used with both tensorflow and torch.\n      Suggestion: Consider using only one backend for better efficiency"
-                                        .to_string(),
-                                );
-                            }
+                        // Check if both tensorflow and torch are present
+                        "transformers"
+                            if dependencies.contains_key("tensorflow")
+                                && dependencies.contains_key("torch") =>
+                        {
+                            conflicts.push(Finding::new(
+                                "mixed-backends",
+                                Severity::Info,
+                                "Warning: transformers is being used with both tensorflow and torch.\n      Suggestion: Consider using only one backend for better efficiency"
+                                    .to_string(),
+                                vec![name.to_string(), "tensorflow".to_string(), "torch".to_string()],
+                                vec![dep.location()],
+                            ));
                         }
                         _ => {}
                     }
@@ -391,7 +1899,135 @@ impl DependencyAnalyzer {
         conflicts
     }
 
-    fn generate_graph(&self, format: &str) -> Result<String> {
+    /// Index of the first configured category matching `name`, if any.
+    fn category_index(&self, name: &str) -> Option<usize> {
+        let name = lockfile::normalize_name(name);
+        self.category_matchers
+            .iter()
+            .position(|matcher| matcher.is_match(&name))
+    }
+
+    /// Background color configured for the cluster labelled `label`.
+    fn category_color(&self, label: &str) -> Option<&str> {
+        self.config
+            .categories
+            .iter()
+            .find(|category| category.name == label)
+            .and_then(|category| category.color.as_deref())
+    }
+
+    /// Graph nodes grouped into (cluster id, label, nodes): one cluster per
+    /// configured category, then "Other", then "Local Projects" for
+    /// workspace members and path dependencies. Nodes are sorted by name.
+    fn graph_clusters(&self) -> Vec<(String, String, Vec<NodeIndex>)> {
+        let mut clusters: Vec<(String, String, Vec<NodeIndex>)> = self
+            .config
+            .categories
+            .iter()
+            .enumerate()
+            .map(|(i, category)| (format!("cluster_{}", i), category.name.clone(), Vec::new()))
+            .collect();
+        clusters.push(("cluster_other".to_string(), "Other".to_string(), Vec::new()));
+        clusters.push(("cluster_local".to_string(), "Local Projects".to_string(), Vec::new()));
+
+        let mut nodes: Vec<NodeIndex> = self.graph.node_indices().collect();
+        nodes.sort_by(|a, b| self.graph[*a].cmp(&self.graph[*b]));
+        for node in nodes {
+            // A collapsed cluster stays in the cluster of its packages
+            let name = &self.graph[node];
+            let name = self.collapsed_members(name).first().copied().unwrap_or(name);
+            let index = if self.local_packages.contains(name) {
+                clusters.len() - 1
+            } else {
+                self.category_index(name).unwrap_or(clusters.len() - 2)
+            };
+            clusters[index].2.push(node);
+        }
+        clusters
+    }
+
+    /// Number of requirements on `name` that could pick different versions.
+    /// Local projects have a single source no matter how often they are required.
+    fn version_count(&self, name: &str) -> usize {
+        self.dependencies
+            .get(name)
+            .map_or(0, |deps| deps.iter().filter(|d| d.local_path.is_none()).count())
+    }
+
+    /// Packages folded into the collapsed cluster node `name`, if it is one.
+    fn collapsed_members(&self, name: &str) -> Vec<&str> {
+        self.collapsed
+            .iter()
+            .filter(|(_, cluster)| *cluster == name)
+            .map(|(package, _)| package.as_str())
+            .collect()
+    }
+
+    /// Conflict status of a graph node: `multiple-versions` for packages
+    /// required with several specs, `known-conflict` for packages in the
+    /// known-conflict table, `ok` otherwise. A collapsed cluster takes the
+    /// worst status of its packages.
+    fn conflict_status(&self, name: &str) -> &'static str {
+        let members = self.collapsed_members(name);
+        if !members.is_empty() {
+            let statuses: Vec<&str> = members.iter().map(|m| self.conflict_status(m)).collect();
+            return ["multiple-versions", "known-conflict"]
+                .into_iter()
+                .find(|status| statuses.contains(status))
+                .unwrap_or("ok");
+        }
+        if self.version_count(name) > 1 {
+            "multiple-versions"
+        } else if self.known_conflicts.contains_key(name) {
+            "known-conflict"
+        } else {
+            "ok"
+        }
+    }
+
+    /// Outline color and label of a graph node: red with a version count for
+    /// packages required with several specs, orange for packages in the
+    /// known-conflict table, dark gray otherwise. Collapsed clusters show
+    /// how many packages they stand for.
+    fn node_style(&self, name: &str) -> (&'static str, String) {
+        let members = self.collapsed_members(name).len();
+        let status = self.conflict_status(name);
+        let label = if members > 0 {
+            format!("{}\n({} packages)", name, members)
+        } else if status == "multiple-versions" {
+            format!("{}\n({} versions)", name, self.version_count(name))
+        } else {
+            name.to_string()
+        };
+        let color = match status {
+            "multiple-versions" => "#CC0000",
+            "known-conflict" => "#FF6600",
+            _ => "#2D3436",
+        };
+        (color, label)
+    }
+
+    /// Graph edges ordered by the names of their endpoints.
+    fn sorted_edges(&self) -> Vec<(NodeIndex, NodeIndex)> {
+        let mut edges: Vec<(NodeIndex, NodeIndex)> = self
+            .graph
+            .edge_indices()
+            .filter_map(|e| self.graph.edge_endpoints(e))
+            .collect();
+        edges.sort_by(|a, b| {
+            (&self.graph[a.0], &self.graph[a.1]).cmp(&(&self.graph[b.0], &self.graph[b.1]))
+        });
+        edges
+    }
+
+    /// Whether an edge joins two packages of the known-conflict table.
+    fn is_conflict_edge(&self, from: NodeIndex, to: NodeIndex) -> bool {
+        self.known_conflicts
+            .get(&self.graph[from])
+            .is_some_and(|conflicts| conflicts.contains(&self.graph[to]))
+    }
+
+    fn generate_graph(&self, format: &str, findings: &[&Finding]) -> Result<String> {
         match format {
             "dot" | "png" => {
                 let mut dot = String::from("digraph dependencies {\n");
@@ -400,81 +2036,33 @@ impl DependencyAnalyzer {
                 dot.push_str("    node [shape=box, style=rounded, fontname=\"Arial\"];\n");
                 dot.push_str("    edge [color=\"#666666\", fontname=\"Arial\"];\n\n");
 
-                // Create subgraphs for package categories
-                let mut ml_packages = Vec::new();
-                let mut data_packages = Vec::new();
-                let mut util_packages = Vec::new();
-                let mut other_packages = Vec::new();
-
-                // Categorize packages
-                for node in self.graph.node_indices() {
-                    let name = &self.graph[node];
-                    match name.as_str() {
-                        "tensorflow" | "torch" | "jax" | "transformers" | "scikit-learn" => {
-                            ml_packages.push(node);
-                        }
-                        "numpy" | "pandas" | "matplotlib" | "seaborn" => {
-                            data_packages.push(node);
-                        }
-                        "tqdm" | "pillow" | "tokenizers" => {
-                            util_packages.push(node);
-                        }
-                        _ => {
-                            other_packages.push(node);
-                        }
-                    }
-                }
-
-                // Helper function to generate node attributes
-                let node_attrs = |name: &str, deps: &Vec<Dependency>| -> String {
-                    let has_conflicts = deps.len() > 1;
-                    let is_known_conflict = self.known_conflicts.contains_key(name);
-                    
-                    let (color, style) = if has_conflicts {
-                        ("#CC0000", "bold") // Red for version conflicts
-                    } else if is_known_conflict {
-                        ("#FF6600", "bold") // Orange for known conflicts
-                    } else {
-                        ("#2D3436", "normal") // Dark gray for normal nodes
-                    };
-
-                    let label = if has_conflicts {
-                        format!("{}\n({} versions)", name, deps.len())
-                    } else {
-                        name.to_string()
-                    };
-
-                    format!(
-                        "color=\"{}\", fontcolor=\"{}\", style=\"rounded,filled\", fillcolor=\"white\", \
-                         fontname=\"Arial\", fontsize=\"10\", margin=\"0.2\", height=\"0.4\", label=\"{}\"",
-                        color, color, label.replace("\"", "\\\"")
-                    )
-                };
+                let clusters = self.graph_clusters();
+                let cycles = self.cycle_membership();
 
                 // Generate subgraphs
-                let subgraphs = [
-                    ("cluster_ml", "Machine Learning", &ml_packages),
-                    ("cluster_data", "Data Processing", &data_packages),
-                    ("cluster_util", "Utilities", &util_packages),
-                    ("cluster_other", "Other", &other_packages),
-                ];
-
-                for (cluster_name, label, packages) in subgraphs.iter() {
+                for (cluster_name, label, packages) in clusters.iter() {
                     if !packages.is_empty() {
                         dot.push_str(&format!("    subgraph {} {{\n", cluster_name));
                         dot.push_str(&format!("        label=\"{}\";\n", label));
                         dot.push_str("        style=rounded;\n");
                         dot.push_str("        color=\"#E0E0E0\";\n");
-                        dot.push_str("        bgcolor=\"#F8F8F8\";\n\n");
+                        dot.push_str(&format!(
+                            "        bgcolor=\"{}\";\n\n",
+                            self.category_color(label).unwrap_or("#F8F8F8")
+                        ));
 
-                        for &node in *packages {
-                            let name = &self.graph[node];
-                            let deps = &self.dependencies[name];
-                            let node_id = format!("n{}", node.index());
+                        for &node in packages {
+                            let (color, label) = self.node_style(&self.graph[node]);
+                            // Packages on a cycle get a thicker outline
+                            let penwidth = if cycles.contains(node) { ", penwidth=\"2.5\"" } else { "" };
                             dot.push_str(&format!(
-                                "        {} [{}];\n",
-                                node_id,
-                                node_attrs(name, deps)
+                                "        n{} [color=\"{}\", fontcolor=\"{}\", style=\"rounded,filled\", fillcolor=\"white\", \
+                                 fontname=\"Arial\", fontsize=\"10\", margin=\"0.2\", height=\"0.4\"{}, label=\"{}\"];\n",
+                                node.index(),
+                                color,
+                                color,
+                                penwidth,
+                                label.replace('"', "\\\"")
                             ));
                         }
                         dot.push_str("    }\n\n");
@@ -482,18 +2070,11 @@ impl DependencyAnalyzer {
                 }
 
                 // Add edges with improved styling
-                for edge in self.graph.edge_indices() {
-                    let (from, to) = self.graph.edge_endpoints(edge).unwrap();
-                    let from_name = &self.graph[from];
-                    let to_name = &self.graph[to];
-                    
-                    let is_conflict = self.known_conflicts
-                        .get(from_name)
-                        .map(|conflicts| conflicts.contains(to_name))
-                        .unwrap_or(false);
-
-                    let style = if is_conflict {
+                for (from, to) in self.sorted_edges() {
+                    let style = if self.is_conflict_edge(from, to) {
                         "color=\"#CC0000\", style=\"dashed\", penwidth=2.0, arrowsize=1.5"
+                    } else if cycles.has_edge(from, to) {
+                        "color=\"#8E44AD\", penwidth=2.0, arrowsize=1.2"
                     } else {
                         "color=\"#666666\", penwidth=1.0, arrowsize=1.0"
                     };
@@ -507,103 +2088,474 @@ impl DependencyAnalyzer {
                 dot.push_str("}\n");
                 Ok(dot)
             }
-            "text" => {
-                let mut output = String::new();
-                for (name, deps) in &self.dependencies {
-                    for dep in deps {
-                        output.push_str(&format!(
-                            "{} ({}) [from {}]\n",
-                            name, dep.version_spec, dep.source_file
-                        ));
-                    }
-                }
-                Ok(output)
+            "svg" => Ok(svg::render(self)),
+            "html" => html::render(self, findings),
+            "json" => {
+                let report = report::build_report(self, findings);
+                Ok(serde_json::to_string_pretty(&report)? + "\n")
             }
+            "sarif" => {
+                let log = sarif::build_log(self, findings);
+                Ok(serde_json::to_string_pretty(&log)? + "\n")
+            }
+            format if sbom::FORMATS.contains(&format) => sbom::build(self).render(format),
+            format if graph_export::FORMATS.contains(&format) => graph_export::render(self, format),
+            "text" => Ok(tree::render(
+                self,
+                self.config.output.tree_depth,
+                self.config.output.invert,
+            )),
             _ => Err(anyhow::anyhow!("Unsupported output format")),
         }
     }
 }
 
+/// Apply command-line flags on top of the loaded configuration.
+fn merge_cli_overrides(config: &mut Config, args: &Args) {
+    if !args.include.is_empty() {
+        config.include = args.include.clone();
+    }
+    config.exclude.extend(args.exclude.iter().cloned());
+    if args.max_depth.is_some() {
+        config.max_depth = args.max_depth;
+    }
+    if args.cross_project {
+        config.cross_project = true;
+    }
+    if args.no_ignore {
+        config.respect_gitignore = false;
+    }
+    if args.no_default_excludes {
+        config.default_excludes = false;
+    }
+    if args.no_follow_links {
+        config.follow_links = false;
+    }
+    if !args.python_versions.is_empty() {
+        config.python_versions = args.python_versions.clone();
+    }
+    if args.min_severity.is_some() {
+        config.severity.min = args.min_severity;
+    }
+    if args.fail_on.is_some() {
+        config.severity.fail_on = args.fail_on;
+    }
+    if args.format.is_some() {
+        config.output.format = args.format.clone();
+    }
+    if args.output.is_some() {
+        config.output.file = args.output.clone();
+    }
+    if args.baseline.is_some() {
+        config.output.baseline = args.baseline.clone();
+    }
+    if args.tree_depth.is_some() {
+        config.output.tree_depth = args.tree_depth;
+    }
+    if args.invert {
+        config.output.invert = true;
+    }
+    if args.focus.is_some() {
+        config.graph.focus = args.focus.clone();
+    }
+    if args.reaching.is_some() {
+        config.graph.reaching = args.reaching.clone();
+    }
+    if args.graph_depth.is_some() {
+        config.graph.depth = args.graph_depth;
+    }
+    if !args.categories.is_empty() {
+        config.graph.categories = args.categories.clone();
+    }
+    if !args.scopes.is_empty() {
+        config.graph.scopes = args.scopes.clone();
+    }
+    if args.hide_leaves {
+        config.graph.hide_leaves = true;
+    }
+    if args.collapse_clusters {
+        config.graph.collapse_clusters = true;
+    }
+    if args.trove_classifiers {
+        config.trove_classifiers = true;
+    }
+}
+
+/// One side of `diff`: a report file, a directory to scan, or a git
+/// revision of the repository at --path, read from the object database.
+fn diff_side(spec: &str, args: &Args) -> Result<(String, diff::Snapshot)> {
+    let path = Path::new(spec);
+    if path.is_file() {
+        return Ok((spec.to_string(), diff::Snapshot::load(path)?));
+    }
+    if path.is_dir() {
+        return Ok((spec.to_string(), scan_snapshot(path, args, FileSystem::Disk)?));
+    }
+
+    // Scan the same directory of the repository --path points at
+    let root = Path::new(args.path.as_deref().unwrap_or("."));
+    let tree = GitTree::open(root, spec).with_context(|| {
+        format!("'{}' is not a directory, a report file or a git revision", spec)
+    })?;
+    let label = format!("{} ({})", spec, &tree.commit()[..7]);
+    Ok((label, scan_snapshot(root, args, FileSystem::Git(tree))?))
+}
+
+/// Scan `path` in `files` with its own configuration (or --config) and the
+/// CLI flags.
+fn scan_snapshot(path: &Path, args: &Args, files: FileSystem) -> Result<diff::Snapshot> {
+    let (config, _) = load_config(path, args, &files)?;
+    let rules = load_rules(&config, args, &files)?;
+    let min_severity = config.severity.min;
+
+    let mut analyzer = DependencyAnalyzer::new(config);
+    analyzer.files = files;
+    analyzer.add_conflict_rules(rules);
+    analyzer.load_categories()?;
+    analyzer
+        .scan_project(path)
+        .with_context(|| format!("Failed to scan {}", path.display()))?;
+    let findings = analyzer.check_conflicts();
+    let reported: Vec<&Finding> = findings
+        .iter()
+        .filter(|f| min_severity.is_none_or(|min| f.severity >= min))
+        .collect();
+    diff::Snapshot::from_report(&report::build_report(&analyzer, &reported), &analyzer.root)
+}
+
+/// Write a rendered report to the output file, or print it to stdout under
+/// an optional heading.
+fn write_report(output: &Option<String>, rendered: &str, heading: Option<&str>) -> Result<()> {
+    match output {
+        Some(output_file) => {
+            fs::write(output_file, rendered)
+                .with_context(|| format!("Failed to write report to {}", output_file))?;
+            status!("\n{}", format!("Report written to {}", output_file).green());
+        }
+        None => {
+            match heading {
+                Some(heading) => status!("\n{}", heading.green()),
+                None => status!(""),
+            }
+            print!("{}", rendered);
+        }
+    }
+    Ok(())
+}
+
+/// Load --config from disk, or the configuration found in `root` of
+/// `files`; CLI flags take precedence over the file.
+fn load_config(root: &Path, args: &Args, files: &FileSystem) -> Result<(Config, Option<PathBuf>)> {
+    let (mut config, config_path) = match &args.config {
+        Some(path) => (
+            Config::load(Path::new(path), &FileSystem::Disk)?,
+            Some(PathBuf::from(path)),
+        ),
+        None => Config::discover(root, files)?,
+    };
+    merge_cli_overrides(&mut config, args);
+    Ok((config, config_path))
+}
+
+/// Read the rules files of `config` from where the config itself came from.
+fn load_rules(config: &Config, args: &Args, files: &FileSystem) -> Result<Vec<ConflictRule>> {
+    match &args.config {
+        Some(_) => config.load_rules(&FileSystem::Disk),
+        None => config.load_rules(files),
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
-    let mut analyzer = DependencyAnalyzer::new();
+
+    // With --rev, the configuration is read from that revision too
+    let root = Path::new(args.path.as_deref().unwrap_or("."));
+    let files = match &args.rev {
+        Some(rev) => FileSystem::Git(GitTree::open(root, rev)?),
+        None => FileSystem::Disk,
+    };
+    let (config, config_path) = load_config(root, &args, &files)?;
+
+    if let Some(Command::Config) = args.command {
+        match &config_path {
+            Some(path) => println!("# Configuration loaded from {}", path.display()),
+            None => println!("# No configuration file found, showing defaults"),
+        }
+        print!("{}", config.to_toml()?);
+        return Ok(());
+    }
+
+    let format = config.output.format.clone().unwrap_or_else(|| "text".to_string());
+    let output = config.output.file.clone();
+    let min_severity = config.severity.min;
+    let fail_on = config.severity.fail_on;
+    let baseline_path = config.output.baseline.clone();
+    if args.update_baseline && baseline_path.is_none() {
+        anyhow::bail!("--update-baseline requires --baseline or output.baseline in the config");
+    }
+
+    // Markdown and CSV reports are piped into other tools just like JSON, so
+    // progress goes to stderr for every subcommand format but text
+    let report_on_stdout = match &args.command {
+        Some(Command::Consistency { format, .. })
+        | Some(Command::Diff { format, .. })
+        | Some(Command::Metrics { format, .. })
+        | Some(Command::Why { format, .. })
+        | Some(Command::History { format, .. }) => format != "text",
+        _ => is_machine_readable(&format),
+    };
+    if output.is_none() && report_on_stdout {
+        STDOUT_IS_REPORT.store(true, Ordering::Relaxed);
+    }
+
+    if let Some(Command::Diff { old, new, format }) = &args.command {
+        let (old_label, old_snapshot) = diff_side(old, &args)?;
+        let (new_label, new_snapshot) = diff_side(new, &args)?;
+        let report = diff::compare(&old_label, &old_snapshot, &new_label, &new_snapshot);
+        let rendered = match format.as_str() {
+            "text" => report.render_text(),
+            "markdown" => report.render_markdown(),
+            "json" => serde_json::to_string_pretty(&report)? + "\n",
+            _ => anyhow::bail!("Unsupported diff format '{}'", format),
+        };
+        write_report(&output, &rendered, None)?;
+        return Ok(());
+    }
+
+    if let Some(Command::History { format, packages }) = &args.command {
+        let root = Path::new(args.path.as_deref().unwrap_or("."));
+        let commits = history::commits(root)?;
+        status!("Scanning manifests at {} commit(s)...", commits.len());
+        let mut timeline = history::Timeline::new(packages);
+        QUIET.store(true, Ordering::Relaxed);
+        for commit in &commits {
+            let tree = GitTree::open(root, &commit.id)?;
+            let snapshot = scan_snapshot(root, &args, FileSystem::Git(tree))
+                .with_context(|| format!("Failed to scan commit {}", &commit.id[..7]))?;
+            timeline.record(commit, snapshot);
+        }
+        QUIET.store(false, Ordering::Relaxed);
+        let report = timeline.finish();
+        let rendered = match format.as_str() {
+            "text" => report.render_text(),
+            "csv" => report.render_csv(),
+            "json" => serde_json::to_string_pretty(&report)? + "\n",
+            _ => anyhow::bail!("Unsupported history format '{}'", format),
+        };
+        write_report(&output, &rendered, None)?;
+        return Ok(());
+    }
+
+    let rules = load_rules(&config, &args, &files)?;
+    let mut analyzer = DependencyAnalyzer::new(config);
+    analyzer.add_conflict_rules(rules);
+    analyzer.load_categories()?;
 
     // Scan project
-    analyzer
-        .scan_project(Path::new(&args.path))
-        .context("Failed to scan project")?;
+    match &args.path {
+        Some(path) => {
+            if let (Some(rev), FileSystem::Git(tree)) = (&args.rev, &files) {
+                status!("Reading {} at {} ({})", path, rev, &tree.commit()[..7]);
+            }
+            analyzer.files = files;
+            analyzer
+                .scan_project(Path::new(path))
+                .context("Failed to scan project")?
+        }
+        None => analyzer.root = PathBuf::from("."),
+    }
+    for sbom_file in &args.sbom {
+        analyzer
+            .import_sbom(Path::new(sbom_file))
+            .with_context(|| format!("Failed to import SBOM {}", sbom_file))?;
+    }
+    if args.strict && !analyzer.diagnostics.is_empty() {
+        anyhow::bail!(
+            "{} dependency file error(s) found (--strict)",
+            analyzer.diagnostics.len()
+        );
+    }
+
+    if let Some(Command::Consistency { format, all }) = &args.command {
+        let report = consistency::build_report(&analyzer, *all);
+        let rendered = match format.as_str() {
+            "text" => report.render_text(),
+            "markdown" => report.render_markdown(),
+            "json" => serde_json::to_string_pretty(&report)? + "\n",
+            _ => anyhow::bail!("Unsupported consistency report format '{}'", format),
+        };
+        write_report(&output, &rendered, Some("Cross-Project Consistency:"))?;
+        return Ok(());
+    }
+
+    if let Some(Command::Metrics { format, top }) = &args.command {
+        let report = metrics::build_report(&analyzer, *top);
+        let rendered = match format.as_str() {
+            "text" => report.render_text(),
+            "markdown" => report.render_markdown(),
+            "json" => serde_json::to_string_pretty(&report)? + "\n",
+            _ => anyhow::bail!("Unsupported metrics report format '{}'", format),
+        };
+        write_report(&output, &rendered, Some("Dependency Graph Metrics:"))?;
+        return Ok(());
+    }
+
+    if let Some(Command::Why {
+        package,
+        format,
+        max_paths,
+    }) = &args.command
+    {
+        let report = why::build_report(&analyzer, package, *max_paths)?;
+        let rendered = match format.as_str() {
+            "text" => report.render_text(),
+            "json" => serde_json::to_string_pretty(&report)? + "\n",
+            _ => anyhow::bail!("Unsupported why report format '{}'", format),
+        };
+        write_report(&output, &rendered, None)?;
+        return Ok(());
+    }
 
     // Check for conflicts
-    let conflicts = analyzer.check_conflicts();
-    if !conflicts.is_empty() {
-        println!("\n{}", "Potential Conflicts Found:".red());
-        for conflict in conflicts {
-            println!("  - {}", conflict);
+    let conflicts: Vec<Finding> = analyzer
+        .check_conflicts()
+        .into_iter()
+        .filter(|f| min_severity.is_none_or(|min| f.severity >= min))
+        .collect();
+    let mut new_findings = 0;
+    let mut reported: Vec<&Finding> = conflicts.iter().collect();
+    match &baseline_path {
+        Some(baseline_file) if args.update_baseline => {
+            let baseline = Baseline::from_findings(&conflicts, &analyzer.root);
+            baseline.save(Path::new(baseline_file))?;
+            status!(
+                "\n{}",
+                format!(
+                    "Baseline with {} finding(s) written to {}",
+                    baseline.findings.len(),
+                    baseline_file
+                )
+                .green()
+            );
+        }
+        Some(baseline_file) => {
+            let baseline = Baseline::load(Path::new(baseline_file))?;
+            let comparison = baseline.compare(&conflicts, &analyzer.root);
+            if !comparison.new.is_empty() {
+                status!("\n{}", "New Conflicts Found (not in baseline):".red());
+                for conflict in &comparison.new {
+                    status!("  - {}", conflict);
+                }
+            }
+            if !comparison.fixed.is_empty() {
+                status!("\n{}", "Fixed Since Baseline:".green());
+                for entry in &comparison.fixed {
+                    status!("  - [{}] {}", entry.rule, entry.message);
+                }
+            }
+            status!(
+                "\n{} new, {} baselined, {} fixed",
+                comparison.new.len(),
+                conflicts.len() - comparison.new.len(),
+                comparison.fixed.len()
+            );
+            new_findings = comparison.new.len();
+            reported = comparison.new;
+        }
+        None => {
+            if !conflicts.is_empty() {
+                status!("\n{}", "Potential Conflicts Found:".red());
+                for conflict in &conflicts {
+                    status!("  - {}", conflict);
+                }
+            }
+        }
+    }
+
+    if analyzer.config.trove_classifiers {
+        status!("\n{}", "Fetching trove classifiers...".cyan());
+        let failed = classifiers::classify(&mut analyzer).await?;
+        if !failed.is_empty() {
+            status!(
+                "{}",
+                format!("Could not fetch classifiers for: {}", failed.join(", ")).yellow()
+            );
         }
     }
 
+    // Narrow the graph down to the requested view
+    let view = analyzer.config.graph.clone();
+    view::apply(&mut analyzer, &view)?;
+
     // Generate and output dependency graph
     let graph_output = analyzer
-        .generate_graph(&args.format)
+        .generate_graph(&format, &reported)
         .context("Failed to generate graph")?;
 
     // Handle output based on format
-    if let Some(output_file) = args.output {
-        if args.format == "dot" || args.format == "png" {
-            // Write the DOT content to a file
-            let dot_file = if output_file.ends_with(".dot") {
-                output_file.clone()
+    if let Some(output_file) = output {
+        if format == "dot" || format == "png" {
+            // Write the DOT content to a file; for a PNG it goes next to the image
+            let output_path = Path::new(&output_file);
+            let is_png = format == "png" || output_file.ends_with(".png");
+            let dot_file = if is_png {
+                output_path.with_extension("dot")
             } else {
-                // If output_file ends with '.png', generate a temporary DOT file
-                let dot_file = output_file.replace(".png", ".dot");
-                dot_file
+                output_path.to_path_buf()
             };
 
             fs::write(&dot_file, &graph_output)
-                .with_context(|| format!("Failed to write graph to {}", dot_file))?;
-            println!("\n{}", format!("Graph written to {}", dot_file).green());
+                .with_context(|| format!("Failed to write graph to {}", dot_file.display()))?;
+            status!("\n{}", format!("Graph written to {}", dot_file.display()).green());
 
             // If format is PNG, generate PNG using 'dot' command
-            if args.format == "png" || output_file.ends_with(".png") {
-                let png_file = if output_file.ends_with(".png") {
-                    output_file.clone()
-                } else {
-                    output_file.replace(".dot", ".png")
-                };
+            if is_png {
+                let png_file = output_path.with_extension("png");
 
-                println!("{}", "Attempting to generate PNG...".cyan());
+                status!("{}", "Attempting to generate PNG...".cyan());
 
                 // Check if 'dot' command is available
                 if which::which("dot").is_err() {
-                    println!(
+                    // Fall back to the built-in renderer so there is still an image
+                    let svg_file = png_file.with_extension("svg");
+                    fs::write(&svg_file, svg::render(&analyzer))
+                        .with_context(|| format!("Failed to write graph to {}", svg_file.display()))?;
+                    status!(
                         "{}",
-                        "Graphviz 'dot' command not found. Please install Graphviz to generate PNG files.".yellow()
+                        format!(
+                            "Graphviz 'dot' command not found; wrote SVG graph {} instead (use --format svg to skip this step).",
+                            svg_file.display()
+                        )
+                        .yellow()
                     );
                 } else {
                     let output = std::process::Command::new("dot")
-                        .args(["-Tpng", &dot_file, "-o", &png_file])
+                        .arg("-Tpng")
+                        .arg(&dot_file)
+                        .arg("-o")
+                        .arg(&png_file)
                         .stderr(Stdio::piped())
                         .output();
 
                     match output {
                         Ok(output) => {
                             if output.status.success() {
-                                println!(
+                                status!(
                                     "{}",
-                                    format!("PNG graph generated: {}", png_file).green()
+                                    format!("PNG graph generated: {}", png_file.display()).green()
                                 );
                             } else {
                                 let err = String::from_utf8_lossy(&output.stderr);
-                                println!(
+                                status!(
                                     "{}",
                                     "Failed to generate PNG. Error from 'dot':".yellow()
                                 );
-                                println!("{}", err);
+                                status!("{}", err);
                             }
                         }
                         Err(e) => {
-                            println!(
+                            status!(
                                 "{}",
                                 format!("Failed to execute 'dot' command: {}", e).yellow()
                             );
@@ -611,31 +2563,241 @@ async fn main() -> Result<()> {
                     }
                 }
             }
-        } else if args.format == "text" {
+        } else if matches!(format.as_str(), "text" | "svg" | "html") || is_machine_readable(&format) {
+            let what = if graph_export::FORMATS.contains(&format.as_str()) {
+                "Graph"
+            } else if is_machine_readable(&format) || format == "html" {
+                "Report"
+            } else {
+                "Graph"
+            };
             fs::write(&output_file, &graph_output)
-                .with_context(|| format!("Failed to write graph to {}", output_file))?;
-            println!("\n{}", format!("Graph written to {}", output_file).green());
+                .with_context(|| format!("Failed to write {} to {}", what.to_lowercase(), output_file))?;
+            status!("\n{}", format!("{} written to {}", what, output_file).green());
         } else {
-            println!("{}", "Unsupported format specified.".red());
+            status!("{}", "Unsupported format specified.".red());
         }
     } else {
         // No output file specified
-        if args.format == "text" {
-            println!("\n{}", "Dependency Graph:".green());
-            println!("{}", graph_output);
+        if format == "text" {
+            status!("\n{}", "Dependency Graph:".green());
+            status!("{}", graph_output);
+        } else if is_machine_readable(&format) {
+            print!("{}", graph_output);
         } else {
-            println!(
+            status!(
                 "{}",
-                "Please specify an output file when using 'dot' or 'png' format.".yellow()
+                "Please specify an output file when using 'dot', 'png', 'svg' or 'html' format.".yellow()
             );
         }
     }
 
     // Security check (placeholder - can be enhanced with actual security DB integration)
     if args.security {
-        println!("\n{}", "Security Check:".yellow());
-        println!("Security checking is not implemented yet");
+        status!("\n{}", "Security Check:".yellow());
+        status!("Security checking is not implemented yet");
+    }
+
+    if let Some(fail_on) = fail_on {
+        let failing = reported.iter().filter(|f| f.severity >= fail_on).count();
+        if failing > 0 {
+            anyhow::bail!("{} finding(s) at or above severity '{}'", failing, fail_on);
+        }
+    } else if new_findings > 0 {
+        anyhow::bail!("{} new finding(s) not present in the baseline", new_findings);
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An analyzer whose graph has exactly `edges`.
+    fn analyzer_with_edges(edges: &[(&str, &str)]) -> DependencyAnalyzer {
+        let mut analyzer = DependencyAnalyzer::new(Config::default());
+        for (from, to) in edges {
+            let (from, to) = (analyzer.ensure_node(from), analyzer.ensure_node(to));
+            analyzer.graph.add_edge(from, to, ());
+        }
+        analyzer
+    }
+
+    #[test]
+    fn cycle_membership_marks_cycle_nodes_and_edges() {
+        let analyzer = analyzer_with_edges(&[
+            ("a", "b"),
+            ("b", "c"),
+            ("c", "a"),
+            ("c", "d"),
+            ("e", "e"),
+        ]);
+        let node = |name: &str| analyzer.node_map[name];
+        let cycles = analyzer.cycle_membership();
+
+        for name in ["a", "b", "c", "e"] {
+            assert!(cycles.contains(node(name)), "{} is on a cycle", name);
+        }
+        assert!(!cycles.contains(node("d")));
+        assert!(cycles.has_edge(node("c"), node("a")));
+        assert!(cycles.has_edge(node("e"), node("e")));
+        assert!(!cycles.has_edge(node("c"), node("d")));
+    }
+
+    fn finding(rule: &'static str, packages: &[&str], files: &[&str]) -> Finding {
+        let locations = files
+            .iter()
+            .map(|file| Location {
+                file: file.to_string(),
+                span: SourceSpan::from_offsets("numpy==1.0\n", 0, 10),
+            })
+            .collect();
+        let packages = packages.iter().map(|p| p.to_string()).collect();
+        Finding::new(rule, Severity::Error, "numpy==1.0".to_string(), packages, locations)
+    }
+
+    #[test]
+    fn fingerprint_ignores_message_spans_and_the_checkout_location() {
+        let (ci, dev) = (Path::new("/ci/repo"), Path::new("/home/dev/repo"));
+        let a = finding("multiple-versions", &["numpy"], &["/ci/repo/svc/setup.cfg"]);
+        let mut b = finding("multiple-versions", &["numpy"], &["/home/dev/repo/svc/setup.cfg"]);
+        b.message = "numpy==1.1".to_string();
+        b.locations[0].span = SourceSpan::default();
+        assert_eq!(a.fingerprint(ci), b.fingerprint(dev));
+        assert_eq!(a.fingerprint(ci).len(), 16);
+    }
+
+    #[test]
+    fn fingerprint_tells_rules_packages_and_files_apart() {
+        let root = Path::new("/repo");
+        let base = finding("known-conflict", &["a", "b"], &["/repo/requirements.txt"]);
+        let others = [
+            finding("multiple-versions", &["a", "b"], &["/repo/requirements.txt"]),
+            finding("known-conflict", &["ab"], &["/repo/requirements.txt"]),
+            finding("known-conflict", &["a", "b"], &["/repo/svc/requirements.txt"]),
+            finding("known-conflict", &["a", "b"], &["/repo/requirements.txt", "/repo/Pipfile"]),
+        ];
+        for other in &others {
+            assert_ne!(base.fingerprint(root), other.fingerprint(root), "{:?}", other.packages);
+        }
+        // Manifests count once each, whatever the order they were found in
+        let files = ["/repo/req.txt", "/repo/Pipfile", "/repo/req.txt"];
+        let twice = finding("known-conflict", &["a", "b"], &files);
+        let once = finding("known-conflict", &["a", "b"], &files[1..]);
+        assert_eq!(twice.fingerprint(root), once.fingerprint(root));
+    }
+
+    /// The text `span` covers in `content`, for spans within one line.
+    fn span_text(content: &str, span: SourceSpan) -> String {
+        assert_eq!(span.line, span.end_line, "span within one line");
+        let line = content.lines().nth(span.line - 1).unwrap_or_default();
+        line.chars()
+            .skip(span.column - 1)
+            .take(span.end_column - span.column)
+            .collect()
+    }
+
+    /// Spans of the requirements parsed from a manifest called `file_name`.
+    fn requirement_spans(file_name: &str, content: &str) -> Vec<(String, String)> {
+        let dir = std::env::temp_dir()
+            .join(format!("dependency-analyzer-spans-{}-{}", std::process::id(), file_name));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(file_name);
+        fs::write(&path, content).unwrap();
+        let mut analyzer = DependencyAnalyzer::new(Config::default());
+        let result = match file_name {
+            "requirements.txt" => analyzer.parse_requirements(&path),
+            "Pipfile" => analyzer.parse_pipfile(&path),
+            "pyproject.toml" => analyzer.parse_pyproject_toml(&path),
+            "environment.yml" => analyzer.parse_conda_yml(&path),
+            _ => unreachable!("no parser for {}", file_name),
+        };
+        fs::remove_dir_all(&dir).unwrap();
+        result.unwrap();
+        let mut spans: Vec<(String, String)> = analyzer
+            .dependencies
+            .values()
+            .flatten()
+            .map(|dep| (dep.name.clone(), span_text(content, dep.span)))
+            .collect();
+        spans.sort();
+        spans
+    }
+
+    fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected
+            .iter()
+            .map(|(name, text)| (name.to_string(), text.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn requirements_spans_skip_indentation_and_comments() {
+        let content = "# pinned\n  requests>=2.0  # http\n-r base.txt\nnumpy==1.26\n";
+        assert_eq!(
+            requirement_spans("requirements.txt", content),
+            pairs(&[("numpy", "numpy==1.26"), ("requests", "requests>=2.0")])
+        );
+    }
+
+    #[test]
+    fn toml_key_spans_match_quoted_keys_within_their_table() {
+        let content = "\
+[tool.poetry.dependencies]
+python = \"^3.10\"
+\"Flask\" = \"^3.0\"  # web
+'numpy' = \"^1.26\"
+
+[tool.poetry.group.dev.dependencies]
+pytest = \"^8.0\"
+flask-debug = \"*\"
+";
+        assert_eq!(
+            requirement_spans("pyproject.toml", content),
+            pairs(&[
+                ("flask", "\"Flask\" = \"^3.0\""),
+                ("flask-debug", "flask-debug = \"*\""),
+                ("numpy", "'numpy' = \"^1.26\""),
+                ("pytest", "pytest = \"^8.0\""),
+            ])
+        );
+    }
+
+    #[test]
+    fn toml_key_is_not_found_past_its_table() {
+        let content = "[packages]\nrequests = \"*\"\n\n[dev-packages]\npytest = \"*\"\n";
+        assert!(find_toml_key(content, "packages", "pytest").is_none());
+        assert!(find_toml_key(content, "packages", "request").is_none());
+        let (start, end) = find_toml_key(content, "dev-packages", "pytest").unwrap();
+        assert_eq!(&content[start..end], "pytest = \"*\"");
+        // A header comment and the name of a longer table don't confuse the lookup
+        let content = "[packages.extra]\nrequests = \"*\"\n[packages] # main\nrequests = \"*\"\n";
+        assert_eq!(find_toml_table(content, "packages"), content.find("] #").map(|i| i + 1));
+    }
+
+    #[test]
+    fn yaml_item_spans_cover_the_value_without_quotes() {
+        let content = "\
+name: env
+dependencies:
+  - python=3.11
+  - \"numpy>=1.26\"  # arrays
+  - 'pandas'
+  - pip
+";
+        assert_eq!(
+            requirement_spans("environment.yml", content),
+            pairs(&[
+                ("numpy", "numpy>=1.26"),
+                ("pandas", "pandas"),
+                ("pip", "pip"),
+                ("python", "python=3.11"),
+            ])
+        );
+        // Whole items only, not prefixes of longer ones
+        let content = "- numpy-base\n- numpy\n";
+        let (start, end) = find_yaml_item(content, "numpy", 0).unwrap();
+        assert_eq!((start, &content[start..end]), (15, "numpy"));
+    }
+}
@@ -0,0 +1,447 @@
+//! Graph metrics for deciding which dependencies to upgrade or audit first:
+//! dependency counts and depth per project, the packages with the most
+//! dependents, the biggest blast radius and the largest subtrees, and the
+//! articulation points holding the graph together.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt::Write;
+
+use crate::table;
+use crate::tree::RequirementGraph;
+use crate::DependencyAnalyzer;
+
+#[derive(Debug, Serialize)]
+pub struct MetricsReport {
+    pub summary: Summary,
+    pub projects: Vec<ProjectMetrics>,
+    /// Packages with the most direct dependents
+    pub fan_in: Vec<PackageCount>,
+    /// Packages with the most direct and indirect dependents, which is
+    /// everything that breaks if the package does
+    pub blast_radius: Vec<BlastRadius>,
+    /// Packages pulling in the most other packages, directly or not
+    pub largest_subtrees: Vec<PackageCount>,
+    /// Packages whose removal splits the graph, sorted by name
+    pub articulation_points: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    pub projects: usize,
+    pub packages: usize,
+    pub edges: usize,
+    /// Packages some project requires itself
+    pub direct_dependencies: usize,
+    /// Packages only reached through other packages
+    pub transitive_dependencies: usize,
+    /// Most steps any dependency is away from the nearest project requiring it
+    pub max_depth: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProjectMetrics {
+    pub project: String,
+    pub name: String,
+    pub direct: usize,
+    pub transitive: usize,
+    pub max_depth: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackageCount {
+    pub package: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BlastRadius {
+    pub package: String,
+    /// Packages and projects depending on the package, directly or not
+    pub dependents: usize,
+    /// Projects among them
+    pub projects: usize,
+}
+
+/// The requirement graph with nodes as indices into the sorted names.
+struct Indexed<'a> {
+    names: Vec<&'a str>,
+    dependencies: Vec<Vec<usize>>,
+    dependents: Vec<Vec<usize>>,
+}
+
+/// Steps from `start` to every node it reaches, `start` excluded.
+fn distances(adjacency: &[Vec<usize>], start: usize) -> BTreeMap<usize, usize> {
+    let mut distances = BTreeMap::new();
+    let mut queue = VecDeque::from([(start, 0)]);
+    while let Some((node, depth)) = queue.pop_front() {
+        for &next in &adjacency[node] {
+            if next != start && !distances.contains_key(&next) {
+                distances.insert(next, depth + 1);
+                queue.push_back((next, depth + 1));
+            }
+        }
+    }
+    distances
+}
+
+/// Everything depending on `package`, directly or not; `projects` are the
+/// project nodes.
+fn blast_radius(graph: &Indexed, package: usize, projects: &BTreeSet<usize>) -> BlastRadius {
+    let affected = distances(&graph.dependents, package);
+    BlastRadius {
+        package: graph.names[package].to_string(),
+        dependents: affected.len(),
+        projects: affected.keys().filter(|n| projects.contains(n)).count(),
+    }
+}
+
+/// Compute the metrics of `analyzer`; rankings keep the `top` entries.
+pub fn build_report(analyzer: &DependencyAnalyzer, top: usize) -> MetricsReport {
+    let graph = RequirementGraph::build(analyzer);
+    let names: Vec<&str> = graph.nodes.iter().map(String::as_str).collect();
+    let index: BTreeMap<&str, usize> = names.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+    let mut dependencies = vec![Vec::new(); names.len()];
+    let mut dependents = vec![Vec::new(); names.len()];
+    for (from, to) in &graph.edges {
+        let (from, to) = (index[from.as_str()], index[to.as_str()]);
+        dependencies[from].push(to);
+        dependents[to].push(from);
+    }
+    let indexed = Indexed {
+        names,
+        dependencies,
+        dependents,
+    };
+
+    let mut projects = Vec::new();
+    let mut direct_all = BTreeSet::new();
+    let mut reached_all = BTreeSet::new();
+    for project in &analyzer.projects {
+        let distances = distances(&indexed.dependencies, index[project.name.as_str()]);
+        let direct = distances.values().filter(|&&d| d == 1).count();
+        direct_all.extend(distances.iter().filter(|(_, &d)| d == 1).map(|(n, _)| *n));
+        reached_all.extend(distances.keys().copied());
+        projects.push(ProjectMetrics {
+            project: project.id.clone(),
+            name: project.name.clone(),
+            direct,
+            transitive: distances.len() - direct,
+            max_depth: distances.values().copied().max().unwrap_or(0),
+        });
+    }
+    projects.sort_by(|a, b| a.project.cmp(&b.project));
+
+    // Rankings cover packages; projects only count as dependents
+    let project_nodes: BTreeSet<usize> = analyzer
+        .projects
+        .iter()
+        .map(|p| index[p.name.as_str()])
+        .collect();
+    let packages: Vec<usize> = analyzer
+        .graph
+        .node_weights()
+        .map(|name| index[name.as_str()])
+        .collect();
+
+    let rank = |mut entries: Vec<PackageCount>| {
+        entries.retain(|e| e.count > 0);
+        entries.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.package.cmp(&b.package)));
+        entries.truncate(top);
+        entries
+    };
+    let fan_in = rank(
+        packages
+            .iter()
+            .map(|&p| PackageCount {
+                package: indexed.names[p].to_string(),
+                count: indexed.dependents[p].len(),
+            })
+            .collect(),
+    );
+    let largest_subtrees = rank(
+        packages
+            .iter()
+            .map(|&p| PackageCount {
+                package: indexed.names[p].to_string(),
+                count: distances(&indexed.dependencies, p).len(),
+            })
+            .collect(),
+    );
+    let mut blast_radius: Vec<BlastRadius> = packages
+        .iter()
+        .map(|&p| blast_radius(&indexed, p, &project_nodes))
+        .filter(|b| b.dependents > 0)
+        .collect();
+    blast_radius.sort_by(|a, b| {
+        (b.dependents, b.projects)
+            .cmp(&(a.dependents, a.projects))
+            .then_with(|| a.package.cmp(&b.package))
+    });
+    blast_radius.truncate(top);
+
+    let package_set: BTreeSet<usize> = packages.iter().copied().collect();
+    let articulation_points = articulation_points(&indexed)
+        .into_iter()
+        .filter(|n| package_set.contains(n))
+        .map(|n| indexed.names[n].to_string())
+        .collect();
+
+    MetricsReport {
+        summary: Summary {
+            projects: analyzer.projects.len(),
+            packages: packages.len(),
+            edges: graph.edges.len(),
+            direct_dependencies: direct_all.len(),
+            transitive_dependencies: reached_all.difference(&direct_all).count(),
+            max_depth: projects.iter().map(|p| p.max_depth).max().unwrap_or(0),
+        },
+        projects,
+        fan_in,
+        blast_radius,
+        largest_subtrees,
+        articulation_points,
+    }
+}
+
+/// Nodes whose removal disconnects the graph with edge directions ignored,
+/// by Tarjan's low-link algorithm.
+fn articulation_points(graph: &Indexed) -> BTreeSet<usize> {
+    /// A node on the search path and the position of its next neighbor.
+    struct Frame {
+        node: usize,
+        parent: Option<usize>,
+        next: usize,
+        children: usize,
+    }
+
+    let count = graph.names.len();
+    let neighbors: Vec<Vec<usize>> = (0..count)
+        .map(|n| {
+            let mut all: Vec<usize> = graph.dependencies[n]
+                .iter()
+                .chain(&graph.dependents[n])
+                .copied()
+                .collect();
+            all.sort_unstable();
+            all.dedup();
+            all
+        })
+        .collect();
+    let mut discovered: Vec<Option<usize>> = vec![None; count];
+    let mut low = vec![0; count];
+    let mut time = 0;
+    let mut points = BTreeSet::new();
+    for root in 0..count {
+        if discovered[root].is_some() {
+            continue;
+        }
+        discovered[root] = Some(time);
+        low[root] = time;
+        time += 1;
+        let mut stack = vec![Frame {
+            node: root,
+            parent: None,
+            next: 0,
+            children: 0,
+        }];
+        while let Some(frame) = stack.last_mut() {
+            let node = frame.node;
+            if let Some(&next) = neighbors[node].get(frame.next) {
+                frame.next += 1;
+                match discovered[next] {
+                    None => {
+                        frame.children += 1;
+                        discovered[next] = Some(time);
+                        low[next] = time;
+                        time += 1;
+                        stack.push(Frame {
+                            node: next,
+                            parent: Some(node),
+                            next: 0,
+                            children: 0,
+                        });
+                    }
+                    Some(found) if Some(next) != frame.parent => {
+                        low[node] = low[node].min(found);
+                    }
+                    Some(_) => {}
+                }
+                continue;
+            }
+            let frame = stack.pop().expect("frame on the stack");
+            match frame.parent {
+                Some(parent) => {
+                    low[parent] = low[parent].min(low[node]);
+                    let grandparent = stack.last().and_then(|f| f.parent);
+                    if grandparent.is_some() && Some(low[node]) >= discovered[parent] {
+                        points.insert(parent);
+                    }
+                }
+                None if frame.children > 1 => {
+                    points.insert(node);
+                }
+                None => {}
+            }
+        }
+    }
+    points
+}
+
+/// Title, header and rows of a report table.
+type Table = (&'static str, Vec<String>, Vec<Vec<String>>);
+
+impl MetricsReport {
+    fn tables(&self) -> Vec<Table> {
+        let header = |cells: &[&str]| cells.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        let counts = |entries: &[PackageCount]| {
+            entries
+                .iter()
+                .map(|e| vec![e.package.clone(), e.count.to_string()])
+                .collect::<Vec<_>>()
+        };
+        vec![
+            (
+                "Projects",
+                header(&["Project", "Path", "Direct", "Transitive", "Max depth"]),
+                self.projects
+                    .iter()
+                    .map(|p| {
+                        vec![
+                            p.name.clone(),
+                            p.project.clone(),
+                            p.direct.to_string(),
+                            p.transitive.to_string(),
+                            p.max_depth.to_string(),
+                        ]
+                    })
+                    .collect(),
+            ),
+            (
+                "Most dependents (fan-in)",
+                header(&["Package", "Dependents"]),
+                counts(&self.fan_in),
+            ),
+            (
+                "Biggest blast radius",
+                header(&["Package", "Affected", "Projects"]),
+                self.blast_radius
+                    .iter()
+                    .map(|b| vec![b.package.clone(), b.dependents.to_string(), b.projects.to_string()])
+                    .collect(),
+            ),
+            (
+                "Largest subtrees",
+                header(&["Package", "Pulls in"]),
+                counts(&self.largest_subtrees),
+            ),
+        ]
+    }
+
+    fn summary_lines(&self) -> [String; 2] {
+        let s = &self.summary;
+        [
+            format!("Projects: {}, packages: {}, edges: {}", s.projects, s.packages, s.edges),
+            format!(
+                "Direct dependencies: {}, transitive: {}, max depth: {}",
+                s.direct_dependencies, s.transitive_dependencies, s.max_depth
+            ),
+        ]
+    }
+
+    fn articulation_line(&self) -> String {
+        if self.articulation_points.is_empty() {
+            "none".to_string()
+        } else {
+            self.articulation_points.join(", ")
+        }
+    }
+
+    pub fn render_text(&self) -> String {
+        let mut output = self.summary_lines().join("\n") + "\n";
+        for (title, header, rows) in self.tables() {
+            if rows.is_empty() {
+                continue;
+            }
+            let _ = write!(output, "\n{}:\n{}", title, table::render_text(&header, &rows));
+        }
+        let _ = writeln!(output, "\nArticulation points: {}", self.articulation_line());
+        output
+    }
+
+    pub fn render_markdown(&self) -> String {
+        let mut output = String::new();
+        for line in self.summary_lines() {
+            let _ = writeln!(output, "- {}", line);
+        }
+        for (title, header, rows) in self.tables() {
+            if rows.is_empty() {
+                continue;
+            }
+            let _ = write!(output, "\n## {}\n\n{}", title, table::render_markdown(&header, &rows));
+        }
+        let _ = writeln!(output, "\n## Articulation points\n\n{}", self.articulation_line());
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An `Indexed` graph over `names` with edges given as index pairs.
+    fn indexed<'a>(names: &[&'a str], edges: &[(usize, usize)]) -> Indexed<'a> {
+        let mut dependencies = vec![Vec::new(); names.len()];
+        let mut dependents = vec![Vec::new(); names.len()];
+        for &(from, to) in edges {
+            dependencies[from].push(to);
+            dependents[to].push(from);
+        }
+        Indexed {
+            names: names.to_vec(),
+            dependencies,
+            dependents,
+        }
+    }
+
+    #[test]
+    fn articulation_points_ignore_edge_direction() {
+        // app -> web -> {http, json}, http -> urllib3, and json <-> http
+        // closing a cycle; tool -> urllib3 keeps a second component joined
+        // through urllib3 only
+        let graph = indexed(
+            &["app", "http", "json", "tool", "urllib3", "web", "lonely"],
+            &[(0, 5), (5, 1), (5, 2), (2, 1), (1, 4), (3, 4)],
+        );
+        let points: Vec<&str> = articulation_points(&graph)
+            .into_iter()
+            .map(|n| graph.names[n])
+            .collect();
+        assert_eq!(points, ["http", "urllib3", "web"]);
+    }
+
+    #[test]
+    fn articulation_points_handle_long_chains() {
+        let names: Vec<String> = (0..10_000).map(|i| format!("p{}", i)).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let edges: Vec<(usize, usize)> = (1..names.len()).map(|i| (i - 1, i)).collect();
+        let graph = indexed(&names, &edges);
+        assert_eq!(articulation_points(&graph).len(), names.len() - 2);
+    }
+
+    #[test]
+    fn blast_radius_counts_indirect_dependents_and_projects() {
+        // Projects app and tool; app -> web -> http -> urllib3, tool -> http
+        let graph = indexed(
+            &["app", "http", "tool", "urllib3", "web"],
+            &[(0, 4), (4, 1), (1, 3), (2, 1)],
+        );
+        let projects = BTreeSet::from([0, 2]);
+        let radius = blast_radius(&graph, 3, &projects);
+        assert_eq!(radius.package, "urllib3");
+        assert_eq!((radius.dependents, radius.projects), (4, 2));
+        let radius = blast_radius(&graph, 4, &projects);
+        assert_eq!((radius.dependents, radius.projects), (1, 1));
+        assert_eq!(blast_radius(&graph, 0, &projects).dependents, 0);
+    }
+}
@@ -0,0 +1,276 @@
+use anyhow::{Context, Result};
+use globset::GlobSet;
+use regex::Regex;
+use std::path::{Component, Path, PathBuf};
+use std::sync::LazyLock;
+
+use crate::vfs::FileSystem;
+use crate::walk::build_globset;
+
+/// Files whose presence makes a directory the root of a Python project.
+pub const PROJECT_MARKERS: &[&str] = &["pyproject.toml", "setup.py", "setup.cfg"];
+
+/// A Python project inside the scanned tree.
+#[derive(Debug, Clone)]
+pub struct Project {
+    /// Project root relative to the scan root, `.` for the scan root itself
+    pub id: String,
+    pub name: String,
+    pub root: PathBuf,
+    /// Declared in `[project]` or `[tool.poetry]`, if static
+    pub version: Option<String>,
+    pub license: Option<String>,
+}
+
+/// A requirement pointing at a directory instead of an index package.
+#[derive(Debug)]
+pub struct LocalRequirement {
+    /// Name given via `name @ ...` or `#egg=name`, if any
+    pub name: Option<String>,
+    pub path: PathBuf,
+    /// Installed in development mode (`-e`/`--editable`)
+    pub editable: bool,
+}
+
+/// A workspace: a root project plus the member projects it pulls in. uv
+/// declares the members in `[tool.uv.workspace]`; Poetry and PDM have no
+/// such table, so their root project lists the members as develop or
+/// editable path dependencies instead.
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    pub tool: &'static str,
+    pub root: PathBuf,
+    /// Globs relative to the workspace root
+    members: GlobSet,
+    exclude: GlobSet,
+}
+
+impl Workspace {
+    pub fn new(
+        tool: &'static str,
+        root: PathBuf,
+        members: &[String],
+        exclude: &[String],
+    ) -> Result<Self> {
+        Ok(Self {
+            tool,
+            root,
+            members: build_globset(members).context("Invalid workspace member glob")?,
+            exclude: build_globset(exclude).context("Invalid workspace exclude glob")?,
+        })
+    }
+
+    /// The workspace root project and every project matched by `members`
+    /// but not by `exclude`.
+    pub fn member_projects<'a>(&self, projects: &'a [Project]) -> Vec<&'a Project> {
+        projects
+            .iter()
+            .filter(|p| match p.root.strip_prefix(&self.root) {
+                Ok(relative) if relative.as_os_str().is_empty() => true,
+                Ok(relative) => {
+                    let relative = crate::relative_path(&relative.display().to_string(), Path::new(""));
+                    self.members.is_match(&relative) && !self.exclude.is_match(&relative)
+                }
+                Err(_) => false,
+            })
+            .collect()
+    }
+}
+
+/// Find the project roots among the directories of `manifests`. Manifests
+/// outside every project root belong to an implicit project at `scan_root`.
+pub fn detect_projects(
+    scan_root: &Path,
+    manifests: &[PathBuf],
+    files: &FileSystem,
+) -> Vec<Project> {
+    let mut roots: Vec<PathBuf> = manifests
+        .iter()
+        .filter(|m| {
+            m.file_name()
+                .is_some_and(|n| PROJECT_MARKERS.contains(&n.to_string_lossy().as_ref()))
+        })
+        .filter_map(|m| m.parent().map(normalize_path))
+        .collect();
+    roots.sort();
+    roots.dedup();
+
+    let scan_root = normalize_path(scan_root);
+    let uncovered = manifests
+        .iter()
+        .any(|m| !roots.iter().any(|root| normalize_path(m).starts_with(root)));
+    if uncovered && !roots.contains(&scan_root) {
+        roots.insert(0, scan_root.clone());
+    }
+
+    roots
+        .into_iter()
+        .map(|root| {
+            let id = match root.strip_prefix(&scan_root) {
+                Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+                Ok(relative) => crate::relative_path(&relative.display().to_string(), Path::new("")),
+                Err(_) => root.display().to_string(),
+            };
+            let name = project_name(&root, files).unwrap_or_else(|| {
+                root.canonicalize()
+                    .unwrap_or_else(|_| root.clone())
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| id.clone())
+            });
+            let metadata = pyproject_metadata(&root, files);
+            let field = |key: &str| -> Option<String> {
+                let value = metadata.as_ref()?.get(key)?;
+                // PEP 621 allows `license = { text = "..." }`
+                value
+                    .as_str()
+                    .or_else(|| value.get("text")?.as_str())
+                    .map(String::from)
+            };
+            Project {
+                version: field("version"),
+                license: field("license"),
+                id,
+                name,
+                root,
+            }
+        })
+        .collect()
+}
+
+/// The project whose root is the closest ancestor of `path`.
+pub fn owning_project<'a>(projects: &'a [Project], path: &Path) -> Option<&'a Project> {
+    let path = normalize_path(path);
+    projects
+        .iter()
+        .filter(|p| path.starts_with(&p.root))
+        .max_by_key(|p| p.root.components().count())
+}
+
+/// The `[project]` table of the pyproject.toml at `root`, falling back to
+/// `[tool.poetry]`.
+fn pyproject_metadata(root: &Path, files: &FileSystem) -> Option<toml::Value> {
+    let content = files.read_to_string(&root.join("pyproject.toml")).ok()?;
+    let mut value = toml::from_str::<toml::Value>(&content).ok()?;
+    let table = value.as_table_mut()?;
+    table.remove("project").or_else(|| {
+        table
+            .remove("tool")?
+            .as_table_mut()?
+            .remove("poetry")
+    })
+}
+
+static CFG_HEADER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^[ \t]*\[([^\]\n]*)\][ \t]*$").expect("valid section header regex")
+});
+
+static CFG_NAME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^[ \t]*name[ \t]*=[ \t]*(\S+)[ \t]*$").expect("valid name regex")
+});
+
+static SETUP_PY_NAME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\bname\s*=\s*['"]([^'"]+)['"]"#).expect("valid name regex")
+});
+
+/// The distribution name declared by the project at `root`.
+fn project_name(root: &Path, files: &FileSystem) -> Option<String> {
+    if let Ok(content) = files.read_to_string(&root.join("pyproject.toml")) {
+        if let Ok(value) = toml::from_str::<toml::Value>(&content) {
+            let name = value
+                .get("project")
+                .and_then(|p| p.get("name"))
+                .or_else(|| value.get("tool")?.get("poetry")?.get("name"))
+                .and_then(|n| n.as_str());
+            if let Some(name) = name {
+                return Some(name.to_string());
+            }
+        }
+    }
+    if let Ok(content) = files.read_to_string(&root.join("setup.cfg")) {
+        // Only the [metadata] section names the package; it ends at the next header
+        let mut sections = CFG_HEADER.captures_iter(&content).peekable();
+        while let Some(caps) = sections.next() {
+            let start = caps.get(0)?.end();
+            let end = sections.peek().and_then(|c| c.get(0)).map_or(content.len(), |m| m.start());
+            if caps[1].trim() != "metadata" {
+                continue;
+            }
+            if let Some(caps) = CFG_NAME.captures(&content[start..end]) {
+                return Some(caps[1].to_string());
+            }
+        }
+    }
+    if let Ok(content) = files.read_to_string(&root.join("setup.py")) {
+        if let Some(caps) = SETUP_PY_NAME.captures(&content) {
+            return Some(caps[1].to_string());
+        }
+    }
+    None
+}
+
+/// Recognize `-e ../lib`, `./lib`, `name @ file:../lib`,
+/// `name @ file:///${PROJECT_ROOT}/lib` (PDM) and `file:///abs/lib#egg=name`
+/// requirements, resolving relative paths against `base` (the directory of
+/// the manifest).
+pub fn parse_local_requirement(requirement: &str, base: &Path) -> Option<LocalRequirement> {
+    let requirement = requirement.replace("file:///${PROJECT_ROOT}/", "file:./");
+    let requirement = requirement.replace("${PROJECT_ROOT}/", "./");
+    let mut requirement = requirement.trim();
+    let mut editable = false;
+    for flag in ["-e ", "--editable ", "--editable="] {
+        if let Some(rest) = requirement.strip_prefix(flag) {
+            requirement = rest.trim();
+            editable = true;
+        }
+    }
+
+    let (mut name, target) = match requirement.split_once(" @ ") {
+        Some((name, url)) => (Some(name.trim().to_string()), url.trim()),
+        None => (None, requirement),
+    };
+
+    let (target, fragment) = match target.split_once('#') {
+        Some((target, fragment)) => (target, Some(fragment)),
+        None => (target, None),
+    };
+    if let Some(egg) = fragment.and_then(|f| f.strip_prefix("egg=")) {
+        name.get_or_insert_with(|| egg.to_string());
+    }
+
+    let path = if let Some(path) = target.strip_prefix("file://") {
+        path
+    } else if let Some(path) = target.strip_prefix("file:") {
+        path
+    } else if target.starts_with('.') || target.starts_with('/') || (editable && !target.contains("://"))
+    {
+        target
+    } else {
+        return None;
+    };
+
+    Some(LocalRequirement {
+        name,
+        path: normalize_path(&base.join(path)),
+        editable,
+    })
+}
+
+/// Resolve `.` and `..` components without touching the filesystem.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if normalized.file_name().is_some() {
+                    normalized.pop();
+                } else {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
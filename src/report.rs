@@ -0,0 +1,219 @@
+//! Machine-readable JSON report. The layout is described by
+//! `docs/report.schema.json`; bump `SCHEMA_VERSION` whenever a field is
+//! removed or changes meaning.
+
+use serde::Serialize;
+
+use crate::{relative_path, DependencyAnalyzer, Finding, Location, Severity, SourceSpan};
+
+/// Version of the report layout, `major.minor`. Minor bumps only add fields.
+pub const SCHEMA_VERSION: &str = "1.0";
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub schema_version: &'static str,
+    pub tool: Tool,
+    /// Scan root as given on the command line
+    pub root: String,
+    pub projects: Vec<ProjectEntry>,
+    pub dependencies: Vec<DependencyEntry>,
+    pub graph: Graph,
+    pub findings: Vec<FindingEntry>,
+    pub diagnostics: Vec<DiagnosticEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Tool {
+    pub name: &'static str,
+    pub version: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProjectEntry {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DependencyEntry {
+    pub name: String,
+    /// Requirement as written in the manifest
+    pub version_spec: String,
+    /// Normalized requirement, if it could be parsed
+    pub version_req: Option<String>,
+    pub resolved_version: Option<String>,
+    pub project: String,
+    pub scope: String,
+    /// Directory of the local project this requirement points at
+    pub local_path: Option<String>,
+    pub location: LocationEntry,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LocationEntry {
+    /// Manifest path relative to the scan root, with forward slashes
+    pub file: String,
+    /// 1-based; absent when the position is unknown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_column: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Graph {
+    pub nodes: Vec<NodeEntry>,
+    pub edges: Vec<EdgeEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NodeEntry {
+    pub id: String,
+    pub category: Option<String>,
+    /// Provided by a project inside the scanned tree
+    pub local: bool,
+    /// Number of requirements on this package
+    pub requirements: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EdgeEntry {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FindingEntry {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    pub packages: Vec<String>,
+    pub locations: Vec<LocationEntry>,
+    /// Same value as in baseline files
+    pub fingerprint: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiagnosticEntry {
+    pub message: String,
+    pub location: LocationEntry,
+}
+
+impl LocationEntry {
+    pub fn new(file: &str, span: SourceSpan, analyzer: &DependencyAnalyzer) -> Self {
+        let known = |value: usize| span.is_known().then_some(value);
+        Self {
+            file: relative_path(file, &analyzer.root),
+            line: known(span.line),
+            column: known(span.column),
+            end_line: known(span.end_line),
+            end_column: known(span.end_column),
+        }
+    }
+
+    fn from_location(location: &Location, analyzer: &DependencyAnalyzer) -> Self {
+        Self::new(&location.file, location.span, analyzer)
+    }
+}
+
+/// Collect everything the analyzer knows into a serializable report.
+pub fn build_report(analyzer: &DependencyAnalyzer, findings: &[&Finding]) -> Report {
+    let projects = analyzer
+        .projects
+        .iter()
+        .map(|p| ProjectEntry {
+            id: p.id.clone(),
+            name: p.name.clone(),
+        })
+        .collect();
+
+    let mut dependencies: Vec<DependencyEntry> = analyzer
+        .dependencies
+        .values()
+        .flatten()
+        .map(|dep| DependencyEntry {
+            name: dep.name.clone(),
+            version_spec: dep.version_spec.clone(),
+            version_req: dep.version_req.as_ref().map(|r| r.to_string()),
+            resolved_version: dep.resolved_version.as_ref().map(|v| v.to_string()),
+            project: dep.project.clone(),
+            scope: dep.scope.clone(),
+            local_path: dep
+                .local_path
+                .as_ref()
+                .map(|p| relative_path(&p.display().to_string(), &analyzer.root)),
+            location: LocationEntry::new(&dep.source_file, dep.span, analyzer),
+        })
+        .collect();
+    dependencies.sort_by(|a, b| {
+        (&a.name, &a.location.file, a.location.line).cmp(&(&b.name, &b.location.file, b.location.line))
+    });
+
+    let mut nodes: Vec<NodeEntry> = analyzer
+        .graph
+        .node_weights()
+        .map(|name| NodeEntry {
+            id: name.clone(),
+            category: analyzer
+                .category_index(name)
+                .map(|i| analyzer.config.categories[i].name.clone()),
+            local: analyzer.local_packages.contains(name),
+            requirements: analyzer.dependencies.get(name).map_or(0, |d| d.len()),
+        })
+        .collect();
+    nodes.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut edges: Vec<EdgeEntry> = analyzer
+        .graph
+        .edge_indices()
+        .filter_map(|e| analyzer.graph.edge_endpoints(e))
+        .map(|(from, to)| EdgeEntry {
+            from: analyzer.graph[from].clone(),
+            to: analyzer.graph[to].clone(),
+        })
+        .collect();
+    edges.sort_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+
+    let findings = findings
+        .iter()
+        .map(|finding| FindingEntry {
+            rule: finding.rule,
+            severity: finding.severity,
+            message: finding.message.clone(),
+            packages: finding.packages.clone(),
+            locations: finding
+                .locations
+                .iter()
+                .map(|l| LocationEntry::from_location(l, analyzer))
+                .collect(),
+            fingerprint: finding.fingerprint(&analyzer.root),
+        })
+        .collect();
+
+    let diagnostics = analyzer
+        .diagnostics
+        .iter()
+        .map(|d| DiagnosticEntry {
+            message: d.message.clone(),
+            location: LocationEntry::new(&d.file, d.span, analyzer),
+        })
+        .collect();
+
+    Report {
+        schema_version: SCHEMA_VERSION,
+        tool: Tool {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+        },
+        root: analyzer.root.display().to_string(),
+        projects,
+        dependencies,
+        graph: Graph { nodes, edges },
+        findings,
+        diagnostics,
+    }
+}
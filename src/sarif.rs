@@ -0,0 +1,220 @@
+//! SARIF 2.1.0 output for code-scanning tools. Results point at the
+//! manifest lines the findings come from, relative to the `%SRCROOT%` base.
+
+use serde_json::{json, Value};
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::{
+    relative_path, Dependency, DependencyAnalyzer, Finding, Location, Severity, SourceSpan, RULES,
+};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Key of the fingerprint in `partialFingerprints`; bump the suffix if the
+/// fingerprint algorithm changes.
+const FINGERPRINT_KEY: &str = "dependencyAnalyzer/v1";
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info => "note",
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    }
+}
+
+/// Percent-encode `path` for use in a URI, keeping the `/` separators.
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => {
+                let _ = write!(encoded, "%{:02X}", byte);
+            }
+        }
+    }
+    encoded
+}
+
+/// `file:` URI of the directory `root`, with a trailing slash. Windows
+/// paths become `file:///C:/...`.
+fn directory_uri(root: &Path) -> String {
+    let path = root.display().to_string().replace('\\', "/");
+    // canonicalize() gives Windows paths the verbatim `\\?\` prefix
+    let path = path.strip_prefix("//?/").unwrap_or(&path).trim_end_matches('/');
+    let (drive, rest) = match path.as_bytes() {
+        [letter, b':', ..] if letter.is_ascii_alphabetic() => path.split_at(2),
+        _ => ("", path),
+    };
+    let slash = if drive.is_empty() { "" } else { "/" };
+    format!("file://{}{}{}/", slash, drive, encode_path(rest))
+}
+
+fn physical_location(location: &Location, analyzer: &DependencyAnalyzer) -> Value {
+    let mut physical = json!({
+        "artifactLocation": {
+            "uri": encode_path(&relative_path(&location.file, &analyzer.root)),
+            "uriBaseId": "%SRCROOT%",
+        },
+    });
+    if location.span.is_known() {
+        physical["region"] = json!({
+            "startLine": location.span.line,
+            "startColumn": location.span.column,
+            "endLine": location.span.end_line,
+            "endColumn": location.span.end_column,
+        });
+    }
+    json!({ "physicalLocation": physical })
+}
+
+/// Where to point a finding without locations of its own: the first
+/// requirement on one of its packages, or else a manifest of a project
+/// among them.
+fn fallback_location(finding: &Finding, analyzer: &DependencyAnalyzer) -> Option<Location> {
+    let required = finding
+        .packages
+        .iter()
+        .find_map(|p| analyzer.dependencies.get(p)?.first().map(Dependency::location));
+    required.or_else(|| {
+        finding.packages.iter().find_map(|package| {
+            let project = analyzer.projects.iter().find(|p| &p.name == package)?;
+            let dep = analyzer.dependencies.values().flatten().find(|d| d.project == project.id)?;
+            Some(Location {
+                file: dep.source_file.clone(),
+                span: SourceSpan::default(),
+            })
+        })
+    })
+}
+
+fn result(finding: &Finding, analyzer: &DependencyAnalyzer) -> Value {
+    let rule_index = RULES.iter().position(|r| r.id == finding.rule);
+    let fallback;
+    let locations = if finding.locations.is_empty() {
+        fallback = fallback_location(finding, analyzer);
+        fallback.as_slice()
+    } else {
+        finding.locations.as_slice()
+    };
+    let locations: Vec<Value> = locations
+        .iter()
+        .map(|l| physical_location(l, analyzer))
+        .collect();
+    json!({
+        "ruleId": finding.rule,
+        "ruleIndex": rule_index,
+        "level": level(finding.severity),
+        "message": { "text": finding.message },
+        "locations": locations,
+        "partialFingerprints": { FINGERPRINT_KEY: finding.fingerprint(&analyzer.root) },
+        "properties": { "packages": finding.packages },
+    })
+}
+
+/// Build a SARIF log with one run holding `findings` as results and the
+/// scan diagnostics as tool execution notifications.
+pub fn build_log(analyzer: &DependencyAnalyzer, findings: &[&Finding]) -> Value {
+    let rules: Vec<Value> = RULES
+        .iter()
+        .map(|rule| {
+            json!({
+                "id": rule.id,
+                "name": rule.name,
+                "shortDescription": { "text": rule.description },
+                "defaultConfiguration": { "level": level(rule.severity) },
+                "properties": { "tags": ["dependencies"] },
+            })
+        })
+        .collect();
+
+    let notifications: Vec<Value> = analyzer
+        .diagnostics
+        .iter()
+        .map(|d| {
+            let location = Location {
+                file: d.file.clone(),
+                span: d.span,
+            };
+            json!({
+                "level": "error",
+                "message": { "text": d.message },
+                "locations": [physical_location(&location, analyzer)],
+            })
+        })
+        .collect();
+
+    let root = analyzer
+        .root
+        .canonicalize()
+        .unwrap_or_else(|_| analyzer.root.clone());
+    let root_uri = directory_uri(&root);
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/sujalsin/dependency_analyzer",
+                    "rules": rules,
+                },
+            },
+            "originalUriBaseIds": {
+                "%SRCROOT%": { "uri": root_uri },
+            },
+            "invocations": [{
+                "executionSuccessful": true,
+                "toolExecutionNotifications": notifications,
+            }],
+            "results": findings.iter().map(|f| result(f, analyzer)).collect::<Vec<_>>(),
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn uris_are_percent_encoded() {
+        assert_eq!(encode_path("svc a/req#1 100%.txt"), "svc%20a/req%231%20100%25.txt");
+        assert_eq!(directory_uri(Path::new("/srv/my repo/")), "file:///srv/my%20repo/");
+        assert_eq!(directory_uri(Path::new("/")), "file:///");
+    }
+
+    #[test]
+    fn windows_roots_get_a_drive_letter_uri() {
+        assert_eq!(directory_uri(Path::new(r"C:\Users\dev\app")), "file:///C:/Users/dev/app/");
+        assert_eq!(directory_uri(Path::new(r"\\?\D:\src")), "file:///D:/src/");
+    }
+
+    #[test]
+    fn findings_without_locations_point_at_a_requirement_on_their_packages() {
+        let mut analyzer = DependencyAnalyzer::new(Config::default());
+        let span = SourceSpan::from_offsets("a\nrequests\n", 2, 10);
+        let manifest = Path::new("requirements.txt");
+        analyzer.add_requirement("requests", manifest, span, "main");
+        let finding = |packages: &[&str]| {
+            Finding::new(
+                "dependency-cycle",
+                Severity::Warning,
+                String::new(),
+                packages.iter().map(|p| p.to_string()).collect(),
+                Vec::new(),
+            )
+        };
+
+        let location = fallback_location(&finding(&["urllib3", "requests"]), &analyzer).unwrap();
+        assert_eq!((location.file.as_str(), location.span), ("requirements.txt", span));
+        assert!(fallback_location(&finding(&["urllib3"]), &analyzer).is_none());
+        let log = result(&finding(&["requests"]), &analyzer);
+        assert_eq!(log["locations"][0]["physicalLocation"]["region"]["startLine"], 2);
+    }
+}
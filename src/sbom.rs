@@ -0,0 +1,652 @@
+//! Software bill of materials export: CycloneDX 1.5 (JSON and XML) and
+//! SPDX 2.3 (JSON and tag-value), built from the scanned dependencies, the
+//! lockfiles next to them and the dependency graph.
+
+use regex::Regex;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::LazyLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::lockfile::normalize_name;
+use crate::DependencyAnalyzer;
+
+/// SBOM formats accepted by `--format`.
+pub const FORMATS: &[&str] = &["cyclonedx-json", "cyclonedx-xml", "spdx-json", "spdx-tag-value"];
+
+#[derive(Debug, Clone)]
+struct Component {
+    bom_ref: String,
+    /// Projects in the scanned tree are applications, everything else a library
+    application: bool,
+    name: String,
+    version: Option<String>,
+    purl: Option<String>,
+    /// `(algorithm, hex digest)` pairs from lockfiles
+    hashes: Vec<(String, String)>,
+    license: Option<String>,
+    /// Required by at least one project outside dev/optional scopes
+    required: bool,
+}
+
+/// Components and their relationships, independent of the output format.
+pub struct Sbom {
+    root: Component,
+    components: Vec<Component>,
+    /// bom-ref -> (dependency bom-ref -> scope of the requirement)
+    dependencies: BTreeMap<String, BTreeMap<String, String>>,
+    timestamp: String,
+    serial: String,
+}
+
+fn project_ref(id: &str) -> String {
+    format!("project:{}", id)
+}
+
+/// Percent-encode a purl name or version segment.
+fn purl_encode(segment: &str) -> String {
+    segment
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '~') {
+                c.to_string()
+            } else {
+                let mut buf = [0; 4];
+                c.encode_utf8(&mut buf)
+                    .bytes()
+                    .map(|b| format!("%{:02X}", b))
+                    .collect()
+            }
+        })
+        .collect()
+}
+
+static SPDX_EXPRESSION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\(?[A-Za-z0-9.+-]+\)?( (AND|OR|WITH) \(?[A-Za-z0-9.+-]+\)?)*$")
+        .expect("valid SPDX expression regex")
+});
+
+/// Whether `license` can be emitted as an SPDX license expression.
+fn is_spdx_expression(license: &str) -> bool {
+    SPDX_EXPRESSION.is_match(license)
+}
+
+/// `SOURCE_DATE_EPOCH` if set, for reproducible documents, otherwise now.
+fn timestamp() -> String {
+    let seconds = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        });
+    let days = (seconds / 86_400) as i64;
+    let secs = seconds % 86_400;
+
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3_600,
+        secs % 3_600 / 60,
+        secs % 60
+    )
+}
+
+/// A UUID derived from the document content, so the same input yields the
+/// same serial number. Marked as a version 8 (custom) UUID.
+fn content_uuid(content: &str) -> String {
+    let digest = Sha256::digest(content.as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    bytes[6] = (bytes[6] & 0x0f) | 0x80;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// Collect components from the projects and dependencies of `analyzer`.
+pub fn build(analyzer: &DependencyAnalyzer) -> Sbom {
+    let mut components: BTreeMap<String, Component> = BTreeMap::new();
+    let mut dependencies: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+    let mut add_edge = |from: &str, to: &str, scope: &str| {
+        let scopes = dependencies.entry(from.to_string()).or_default();
+        let current = scopes.entry(to.to_string()).or_insert_with(|| scope.to_string());
+        // A main requirement wins over dev or optional ones
+        if scope == "main" {
+            *current = scope.to_string();
+        }
+    };
+
+    for project in &analyzer.projects {
+        let bom_ref = project_ref(&project.id);
+        components.insert(
+            bom_ref.clone(),
+            Component {
+                bom_ref,
+                application: true,
+                name: project.name.clone(),
+                version: project.version.clone(),
+                purl: None,
+                hashes: Vec::new(),
+                license: project.license.clone(),
+                required: true,
+            },
+        );
+    }
+
+    // Without a project at the scan root, describe the tree as a whole
+    let root_ref = project_ref(".");
+    if !components.contains_key(&root_ref) {
+        let name = analyzer
+            .root
+            .canonicalize()
+            .ok()
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+            .unwrap_or_else(|| analyzer.root.display().to_string());
+        components.insert(
+            root_ref.clone(),
+            Component {
+                bom_ref: root_ref.clone(),
+                application: true,
+                name,
+                version: None,
+                purl: None,
+                hashes: Vec::new(),
+                license: None,
+                required: true,
+            },
+        );
+    }
+    // The root describes the whole tree, so every other project is part of it
+    for project in analyzer.projects.iter().filter(|p| p.id != ".") {
+        add_edge(&root_ref, &project_ref(&project.id), "main");
+    }
+
+    let mut refs_by_name: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for dep in analyzer.dependencies.values().flatten() {
+        let from = project_ref(&dep.project);
+        if let Some(local_path) = &dep.local_path {
+            if let Some(target) = analyzer.projects.iter().find(|p| &p.root == local_path) {
+                if target.id != dep.project {
+                    add_edge(&from, &project_ref(&target.id), &dep.scope);
+                }
+            }
+            continue;
+        }
+
+        let ecosystem = dep.ecosystem();
+        let name = if ecosystem == "pypi" {
+            normalize_name(&dep.name)
+        } else {
+            dep.name.to_lowercase()
+        };
+        let locked = analyzer.locked_package(dep);
+        let version = locked
+            .map(|l| l.version.clone())
+            .or_else(|| dep.pinned_version());
+        let purl = match &version {
+            Some(version) => format!(
+                "pkg:{}/{}@{}",
+                ecosystem,
+                purl_encode(&name),
+                purl_encode(version)
+            ),
+            None => format!("pkg:{}/{}", ecosystem, purl_encode(&name)),
+        };
+
+        let component = components.entry(purl.clone()).or_insert_with(|| Component {
+            bom_ref: purl.clone(),
+            application: false,
+            name: name.clone(),
+            version,
+            purl: Some(purl.clone()),
+            hashes: locked.map(|l| l.hashes.clone()).unwrap_or_default(),
+            license: None,
+            required: false,
+        });
+        component.required |= dep.scope == "main";
+        refs_by_name
+            .entry(dep.name.clone())
+            .or_default()
+            .insert(purl.clone());
+        add_edge(&from, &purl, &dep.scope);
+    }
+
+    // Package-to-package edges from the dependency graph
+    for edge in analyzer.graph.edge_indices() {
+        let Some((from, to)) = analyzer.graph.edge_endpoints(edge) else {
+            continue;
+        };
+        let (Some(from_refs), Some(to_refs)) = (
+            refs_by_name.get(&analyzer.graph[from]),
+            refs_by_name.get(&analyzer.graph[to]),
+        ) else {
+            continue;
+        };
+        for from_ref in from_refs {
+            // Prefer the dependency from the same ecosystem
+            let ecosystem = &from_ref[..from_ref.find('/').unwrap_or(0)];
+            let same: Vec<&String> = to_refs.iter().filter(|r| r.starts_with(ecosystem)).collect();
+            let targets = if same.is_empty() { to_refs.iter().collect() } else { same };
+            for to_ref in targets {
+                add_edge(from_ref, to_ref, "main");
+            }
+        }
+    }
+
+    let root = components
+        .remove(&root_ref)
+        .expect("root component is always present");
+    let components: Vec<Component> = components.into_values().collect();
+
+    let mut identity = root.name.clone();
+    for component in &components {
+        identity.push('\n');
+        identity.push_str(&component.bom_ref);
+    }
+    Sbom {
+        serial: content_uuid(&identity),
+        root,
+        components,
+        dependencies,
+        timestamp: timestamp(),
+    }
+}
+
+impl Sbom {
+    pub fn render(&self, format: &str) -> anyhow::Result<String> {
+        match format {
+            "cyclonedx-json" => Ok(serde_json::to_string_pretty(&self.cyclonedx_json())? + "\n"),
+            "cyclonedx-xml" => Ok(self.cyclonedx_xml()),
+            "spdx-json" => Ok(serde_json::to_string_pretty(&self.spdx_json())? + "\n"),
+            "spdx-tag-value" => Ok(self.spdx_tag_value()),
+            _ => Err(anyhow::anyhow!("Unsupported SBOM format '{}'", format)),
+        }
+    }
+
+    fn all_components(&self) -> impl Iterator<Item = &Component> {
+        std::iter::once(&self.root).chain(&self.components)
+    }
+
+    // CycloneDX 1.5
+
+    fn cyclonedx_component(component: &Component) -> Value {
+        let mut value = json!({
+            "type": if component.application { "application" } else { "library" },
+            "bom-ref": component.bom_ref,
+            "name": component.name,
+        });
+        if let Some(version) = &component.version {
+            value["version"] = json!(version);
+        }
+        if !component.application && !component.required {
+            value["scope"] = json!("optional");
+        }
+        let hashes: Vec<Value> = component
+            .hashes
+            .iter()
+            .filter_map(|(alg, digest)| {
+                Some(json!({ "alg": cyclonedx_algorithm(alg)?, "content": digest }))
+            })
+            .collect();
+        if !hashes.is_empty() {
+            value["hashes"] = json!(hashes);
+        }
+        if let Some(license) = &component.license {
+            value["licenses"] = if is_spdx_expression(license) {
+                json!([{ "expression": license }])
+            } else {
+                json!([{ "license": { "name": license } }])
+            };
+        }
+        if let Some(purl) = &component.purl {
+            value["purl"] = json!(purl);
+        }
+        value
+    }
+
+    fn cyclonedx_json(&self) -> Value {
+        let dependencies: Vec<Value> = self
+            .all_components()
+            .map(|c| {
+                let depends_on: Vec<&String> = self
+                    .dependencies
+                    .get(&c.bom_ref)
+                    .map(|d| d.keys().collect())
+                    .unwrap_or_default();
+                json!({ "ref": c.bom_ref, "dependsOn": depends_on })
+            })
+            .collect();
+
+        json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.5",
+            "serialNumber": format!("urn:uuid:{}", self.serial),
+            "version": 1,
+            "metadata": {
+                "timestamp": self.timestamp,
+                "tools": {
+                    "components": [{
+                        "type": "application",
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION"),
+                    }],
+                },
+                "component": Self::cyclonedx_component(&self.root),
+            },
+            "components": self.components.iter().map(Self::cyclonedx_component).collect::<Vec<_>>(),
+            "dependencies": dependencies,
+        })
+    }
+
+    fn cyclonedx_xml_component(component: &Component, indent: &str) -> String {
+        let mut xml = format!(
+            "{}<component type=\"{}\" bom-ref=\"{}\">\n",
+            indent,
+            if component.application { "application" } else { "library" },
+            xml_escape(&component.bom_ref)
+        );
+        let inner = format!("{}  ", indent);
+        xml.push_str(&format!("{}<name>{}</name>\n", inner, xml_escape(&component.name)));
+        if let Some(version) = &component.version {
+            xml.push_str(&format!("{}<version>{}</version>\n", inner, xml_escape(version)));
+        }
+        if !component.application && !component.required {
+            xml.push_str(&format!("{}<scope>optional</scope>\n", inner));
+        }
+        let hashes: Vec<String> = component
+            .hashes
+            .iter()
+            .filter_map(|(alg, digest)| {
+                Some(format!(
+                    "{}  <hash alg=\"{}\">{}</hash>\n",
+                    inner,
+                    cyclonedx_algorithm(alg)?,
+                    xml_escape(digest)
+                ))
+            })
+            .collect();
+        if !hashes.is_empty() {
+            xml.push_str(&format!("{}<hashes>\n{}{}</hashes>\n", inner, hashes.concat(), inner));
+        }
+        if let Some(license) = &component.license {
+            let entry = if is_spdx_expression(license) {
+                format!("<expression>{}</expression>", xml_escape(license))
+            } else {
+                format!("<license><name>{}</name></license>", xml_escape(license))
+            };
+            xml.push_str(&format!("{}<licenses>{}</licenses>\n", inner, entry));
+        }
+        if let Some(purl) = &component.purl {
+            xml.push_str(&format!("{}<purl>{}</purl>\n", inner, xml_escape(purl)));
+        }
+        xml.push_str(&format!("{}</component>\n", indent));
+        xml
+    }
+
+    fn cyclonedx_xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<bom xmlns=\"http://cyclonedx.org/schema/bom/1.5\" serialNumber=\"urn:uuid:{}\" version=\"1\">\n",
+            self.serial
+        ));
+        xml.push_str("  <metadata>\n");
+        xml.push_str(&format!("    <timestamp>{}</timestamp>\n", self.timestamp));
+        xml.push_str("    <tools>\n      <components>\n");
+        xml.push_str(&format!(
+            "        <component type=\"application\">\n          <name>{}</name>\n          <version>{}</version>\n        </component>\n",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        ));
+        xml.push_str("      </components>\n    </tools>\n");
+        xml.push_str(&Self::cyclonedx_xml_component(&self.root, "    "));
+        xml.push_str("  </metadata>\n");
+
+        xml.push_str("  <components>\n");
+        for component in &self.components {
+            xml.push_str(&Self::cyclonedx_xml_component(component, "    "));
+        }
+        xml.push_str("  </components>\n");
+
+        xml.push_str("  <dependencies>\n");
+        for component in self.all_components() {
+            let depends_on = self.dependencies.get(&component.bom_ref);
+            match depends_on {
+                Some(depends_on) if !depends_on.is_empty() => {
+                    xml.push_str(&format!(
+                        "    <dependency ref=\"{}\">\n",
+                        xml_escape(&component.bom_ref)
+                    ));
+                    for target in depends_on.keys() {
+                        xml.push_str(&format!("      <dependency ref=\"{}\"/>\n", xml_escape(target)));
+                    }
+                    xml.push_str("    </dependency>\n");
+                }
+                _ => xml.push_str(&format!(
+                    "    <dependency ref=\"{}\"/>\n",
+                    xml_escape(&component.bom_ref)
+                )),
+            }
+        }
+        xml.push_str("  </dependencies>\n");
+        xml.push_str("</bom>\n");
+        xml
+    }
+
+    // SPDX 2.3
+
+    fn namespace(&self) -> String {
+        format!(
+            "https://spdx.org/spdxdocs/{}-{}",
+            purl_encode(&self.root.name),
+            self.serial
+        )
+    }
+
+    /// `(subject, relationship, object)` triples, starting with DESCRIBES.
+    fn spdx_relationships(&self) -> Vec<(String, &'static str, String)> {
+        let mut relationships = vec![(
+            "SPDXRef-DOCUMENT".to_string(),
+            "DESCRIBES",
+            spdx_id(&self.root.bom_ref),
+        )];
+        for (from, targets) in &self.dependencies {
+            for (to, scope) in targets {
+                let (from, to) = (spdx_id(from), spdx_id(to));
+                relationships.push(match scope.as_str() {
+                    "main" => (from, "DEPENDS_ON", to),
+                    s if s.starts_with("optional:") => (to, "OPTIONAL_DEPENDENCY_OF", from),
+                    _ => (to, "DEV_DEPENDENCY_OF", from),
+                });
+            }
+        }
+        relationships
+    }
+
+    fn spdx_json(&self) -> Value {
+        let packages: Vec<Value> = self
+            .all_components()
+            .map(|c| {
+                let mut package = json!({
+                    "name": c.name,
+                    "SPDXID": spdx_id(&c.bom_ref),
+                    "downloadLocation": "NOASSERTION",
+                    "filesAnalyzed": false,
+                    "licenseConcluded": "NOASSERTION",
+                    "licenseDeclared": spdx_license(c),
+                    "copyrightText": "NOASSERTION",
+                    "primaryPackagePurpose": if c.application { "APPLICATION" } else { "LIBRARY" },
+                });
+                if let Some(version) = &c.version {
+                    package["versionInfo"] = json!(version);
+                }
+                let checksums: Vec<Value> = c
+                    .hashes
+                    .iter()
+                    .filter_map(|(alg, digest)| {
+                        Some(json!({ "algorithm": spdx_algorithm(alg)?, "checksumValue": digest }))
+                    })
+                    .collect();
+                if !checksums.is_empty() {
+                    package["checksums"] = json!(checksums);
+                }
+                if let Some(purl) = &c.purl {
+                    package["externalRefs"] = json!([{
+                        "referenceCategory": "PACKAGE-MANAGER",
+                        "referenceType": "purl",
+                        "referenceLocator": purl,
+                    }]);
+                }
+                package
+            })
+            .collect();
+
+        let relationships: Vec<Value> = self
+            .spdx_relationships()
+            .into_iter()
+            .map(|(subject, kind, object)| {
+                json!({
+                    "spdxElementId": subject,
+                    "relationshipType": kind,
+                    "relatedSpdxElement": object,
+                })
+            })
+            .collect();
+
+        json!({
+            "spdxVersion": "SPDX-2.3",
+            "dataLicense": "CC0-1.0",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": self.root.name,
+            "documentNamespace": self.namespace(),
+            "creationInfo": {
+                "created": self.timestamp,
+                "creators": [format!("Tool: {}-{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))],
+            },
+            "packages": packages,
+            "relationships": relationships,
+        })
+    }
+
+    fn spdx_tag_value(&self) -> String {
+        let mut out = String::new();
+        out.push_str("SPDXVersion: SPDX-2.3\n");
+        out.push_str("DataLicense: CC0-1.0\n");
+        out.push_str("SPDXID: SPDXRef-DOCUMENT\n");
+        out.push_str(&format!("DocumentName: {}\n", self.root.name));
+        out.push_str(&format!("DocumentNamespace: {}\n", self.namespace()));
+        out.push_str(&format!(
+            "Creator: Tool: {}-{}\n",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        ));
+        out.push_str(&format!("Created: {}\n", self.timestamp));
+
+        for c in self.all_components() {
+            out.push('\n');
+            out.push_str(&format!("PackageName: {}\n", c.name));
+            out.push_str(&format!("SPDXID: {}\n", spdx_id(&c.bom_ref)));
+            if let Some(version) = &c.version {
+                out.push_str(&format!("PackageVersion: {}\n", version));
+            }
+            out.push_str("PackageDownloadLocation: NOASSERTION\n");
+            out.push_str("FilesAnalyzed: false\n");
+            for (alg, digest) in &c.hashes {
+                if let Some(alg) = spdx_algorithm(alg) {
+                    out.push_str(&format!("PackageChecksum: {}: {}\n", alg, digest));
+                }
+            }
+            out.push_str("PackageLicenseConcluded: NOASSERTION\n");
+            out.push_str(&format!("PackageLicenseDeclared: {}\n", spdx_license(c)));
+            out.push_str("PackageCopyrightText: NOASSERTION\n");
+            if let Some(purl) = &c.purl {
+                out.push_str(&format!("ExternalRef: PACKAGE-MANAGER purl {}\n", purl));
+            }
+            out.push_str(&format!(
+                "PrimaryPackagePurpose: {}\n",
+                if c.application { "APPLICATION" } else { "LIBRARY" }
+            ));
+        }
+
+        out.push('\n');
+        for (subject, kind, object) in self.spdx_relationships() {
+            out.push_str(&format!("Relationship: {} {} {}\n", subject, kind, object));
+        }
+        out
+    }
+}
+
+fn cyclonedx_algorithm(algorithm: &str) -> Option<&'static str> {
+    match algorithm {
+        "md5" => Some("MD5"),
+        "sha1" => Some("SHA-1"),
+        "sha256" => Some("SHA-256"),
+        "sha384" => Some("SHA-384"),
+        "sha512" => Some("SHA-512"),
+        _ => None,
+    }
+}
+
+fn spdx_algorithm(algorithm: &str) -> Option<&'static str> {
+    match algorithm {
+        "md5" => Some("MD5"),
+        "sha1" => Some("SHA1"),
+        "sha256" => Some("SHA256"),
+        "sha384" => Some("SHA384"),
+        "sha512" => Some("SHA512"),
+        _ => None,
+    }
+}
+
+fn spdx_license(component: &Component) -> String {
+    match &component.license {
+        Some(license) if is_spdx_expression(license) => license.clone(),
+        _ => "NOASSERTION".to_string(),
+    }
+}
+
+/// SPDX element ids may only contain letters, digits, `.` and `-`. Other
+/// characters become `-`, and a hash of the bom-ref keeps refs that only
+/// differ in those characters (`project:a/b`, `project:a-b`) apart.
+fn spdx_id(bom_ref: &str) -> String {
+    let id: String = bom_ref
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '-' })
+        .collect();
+    if id == bom_ref {
+        return format!("SPDXRef-{}", id);
+    }
+    let digest = Sha256::digest(bom_ref.as_bytes());
+    let suffix: String = digest[..4].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("SPDXRef-{}-{}", id, suffix)
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,497 @@
+//! Read CycloneDX (JSON or XML) and SPDX (JSON or tag-value) documents
+//! supplied by vendors, so their components can be analyzed like scanned
+//! manifests.
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::{find_quoted, SourceSpan};
+
+/// A component listed in an SBOM.
+#[derive(Debug)]
+pub struct ImportedComponent {
+    pub name: String,
+    pub version: Option<String>,
+    /// `main`, or `optional:sbom` for components marked optional
+    pub scope: &'static str,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug)]
+pub struct ImportedSbom {
+    /// Name of the software the document describes
+    pub name: String,
+    pub version: Option<String>,
+    pub components: Vec<ImportedComponent>,
+    /// (dependent, dependency) component names
+    pub edges: Vec<(String, String)>,
+}
+
+/// Load an SBOM, detecting the format from the content.
+pub fn load(path: &Path) -> Result<ImportedSbom> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let trimmed = content.trim_start();
+    if trimmed.starts_with('{') {
+        let document: Value = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        if document.get("bomFormat").and_then(|f| f.as_str()) == Some("CycloneDX") {
+            Ok(cyclonedx_json(&document, &content))
+        } else if document.get("spdxVersion").is_some() {
+            Ok(spdx_json(&document, &content))
+        } else {
+            Err(anyhow!("{} is neither a CycloneDX nor an SPDX document", path.display()))
+        }
+    } else if trimmed.starts_with('<') {
+        cyclonedx_xml(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    } else if trimmed.starts_with("SPDXVersion:") || content.contains("\nSPDXVersion:") {
+        Ok(spdx_tag_value(&content))
+    } else {
+        Err(anyhow!("{} is not a recognized SBOM format", path.display()))
+    }
+}
+
+/// Package type and name from a purl such as `pkg:pypi/Foo_Bar@1.0?x=y`.
+fn parse_purl(purl: &str) -> Option<(String, String)> {
+    let rest = purl.strip_prefix("pkg:")?;
+    let rest = rest.split(['?', '#']).next()?;
+    let (kind, path) = rest.split_once('/')?;
+    let name = path.rsplit('/').next()?.split('@').next()?;
+    Some((kind.to_lowercase(), percent_decode(name)))
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Some(Ok(byte)) = text.get(i + 1..i + 3).map(|h| u8::from_str_radix(h, 16)) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Name for a component: from its purl when present, else the declared name.
+/// Components whose purl is not a PyPI one (npm, maven, conda, ...) are no
+/// Python requirements and have none.
+fn component_name(name: Option<&str>, purl: Option<&str>) -> Option<String> {
+    let name = match purl.and_then(parse_purl) {
+        Some((kind, _)) if kind != "pypi" => return None,
+        Some((_, name)) => Some(name),
+        None => name.map(|n| n.trim().to_string()),
+    };
+    name.filter(|n| !n.is_empty())
+}
+
+/// Span of the first quoted occurrence of `needle` at or after `cursor`.
+fn json_span(content: &str, needle: &str, cursor: &mut usize) -> SourceSpan {
+    match find_quoted(content, needle, *cursor) {
+        Some((start, end)) => {
+            *cursor = end;
+            SourceSpan::from_offsets(content, start, end)
+        }
+        None => SourceSpan::default(),
+    }
+}
+
+fn cyclonedx_json(document: &Value, content: &str) -> ImportedSbom {
+    let metadata = document.get("metadata").and_then(|m| m.get("component"));
+    let str_field = |value: Option<&Value>, key: &str| {
+        value
+            .and_then(|v| v.get(key))
+            .and_then(|v| v.as_str())
+            .map(String::from)
+    };
+
+    let mut names: HashMap<String, String> = HashMap::new();
+    let mut components = Vec::new();
+    let mut cursor = content.find("\"components\"").unwrap_or(0);
+    // Nested components are flattened; they are still part of the software
+    let mut stack: Vec<&Value> = document
+        .get("components")
+        .and_then(|c| c.as_array())
+        .map(|c| c.iter().rev().collect())
+        .unwrap_or_default();
+    while let Some(component) = stack.pop() {
+        if let Some(children) = component.get("components").and_then(|c| c.as_array()) {
+            stack.extend(children.iter().rev());
+        }
+        let name = component.get("name").and_then(|n| n.as_str());
+        let purl = component.get("purl").and_then(|p| p.as_str());
+        let Some(package) = component_name(name, purl) else {
+            continue;
+        };
+        let scope = component.get("scope").and_then(|s| s.as_str());
+        if scope == Some("excluded") {
+            continue;
+        }
+        if let Some(bom_ref) = component.get("bom-ref").and_then(|r| r.as_str()) {
+            names.insert(bom_ref.to_string(), package.clone());
+        }
+        let span = json_span(content, purl.or(name).unwrap_or(&package), &mut cursor);
+        components.push(ImportedComponent {
+            name: package,
+            version: component
+                .get("version")
+                .and_then(|v| v.as_str())
+                .map(String::from),
+            scope: if scope == Some("optional") { "optional:sbom" } else { "main" },
+            span,
+        });
+    }
+
+    let mut edges = Vec::new();
+    for entry in document
+        .get("dependencies")
+        .and_then(|d| d.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let Some(from) = entry
+            .get("ref")
+            .and_then(|r| r.as_str())
+            .and_then(|r| names.get(r))
+        else {
+            continue;
+        };
+        for to in entry
+            .get("dependsOn")
+            .and_then(|d| d.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|r| r.as_str())
+            .filter_map(|r| names.get(r))
+        {
+            edges.push((from.clone(), to.clone()));
+        }
+    }
+
+    ImportedSbom {
+        name: str_field(metadata, "name").unwrap_or_else(|| "sbom".to_string()),
+        version: str_field(metadata, "version"),
+        components,
+        edges,
+    }
+}
+
+fn child<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &str) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|c| c.tag_name().name() == name)
+}
+
+fn text(node: roxmltree::Node<'_, '_>, name: &str) -> Option<String> {
+    child(node, name)
+        .and_then(|c| c.text())
+        .map(|t| t.trim().to_string())
+}
+
+fn cyclonedx_xml(content: &str) -> Result<ImportedSbom> {
+    let document = roxmltree::Document::parse(content)?;
+    let bom = document.root_element();
+    if bom.tag_name().name() != "bom" {
+        return Err(anyhow!("root element is not <bom>"));
+    }
+    let metadata = child(bom, "metadata").and_then(|m| child(m, "component"));
+    let mut names: HashMap<String, String> = HashMap::new();
+    let mut components = Vec::new();
+    if let Some(list) = child(bom, "components") {
+        for component in list
+            .descendants()
+            .filter(|n| n.tag_name().name() == "component")
+        {
+            let name = text(component, "name");
+            let purl = text(component, "purl");
+            let Some(package) = component_name(name.as_deref(), purl.as_deref()) else {
+                continue;
+            };
+            let scope = text(component, "scope");
+            if scope.as_deref() == Some("excluded") {
+                continue;
+            }
+            if let Some(bom_ref) = component.attribute("bom-ref") {
+                names.insert(bom_ref.to_string(), package.clone());
+            }
+            let range = child(component, "name").unwrap_or(component).range();
+            components.push(ImportedComponent {
+                name: package,
+                version: text(component, "version"),
+                scope: if scope.as_deref() == Some("optional") {
+                    "optional:sbom"
+                } else {
+                    "main"
+                },
+                span: SourceSpan::from_offsets(content, range.start, range.end),
+            });
+        }
+    }
+
+    let mut edges = Vec::new();
+    if let Some(dependencies) = child(bom, "dependencies") {
+        for entry in dependencies.children().filter(|n| n.is_element()) {
+            let Some(from) = entry.attribute("ref").and_then(|r| names.get(r)) else {
+                continue;
+            };
+            for target in entry.children().filter(|n| n.is_element()) {
+                if let Some(to) = target.attribute("ref").and_then(|r| names.get(r)) {
+                    edges.push((from.clone(), to.clone()));
+                }
+            }
+        }
+    }
+
+    Ok(ImportedSbom {
+        name: metadata
+            .and_then(|m| text(m, "name"))
+            .unwrap_or_else(|| "sbom".to_string()),
+        version: metadata.and_then(|m| text(m, "version")),
+        components,
+        edges,
+    })
+}
+
+/// Turn an SPDX relationship into a (dependent, dependency) pair of ids.
+fn spdx_edge<'a>(subject: &'a str, kind: &str, object: &'a str) -> Option<(&'a str, &'a str)> {
+    match kind {
+        "DEPENDS_ON" => Some((subject, object)),
+        kind if kind.ends_with("DEPENDENCY_OF") => Some((object, subject)),
+        _ => None,
+    }
+}
+
+/// SPDX package fields shared by the JSON and tag-value readers.
+struct SpdxPackage {
+    id: String,
+    name: Option<String>,
+    version: Option<String>,
+    purl: Option<String>,
+    span: SourceSpan,
+}
+
+fn spdx_document(
+    packages: Vec<SpdxPackage>,
+    relationships: Vec<(String, String, String)>,
+    document_name: Option<String>,
+) -> ImportedSbom {
+    // Packages the document describes are the software itself, not components
+    let described: Vec<&str> = relationships
+        .iter()
+        .filter(|(subject, kind, _)| subject == "SPDXRef-DOCUMENT" && kind == "DESCRIBES")
+        .map(|(_, _, object)| object.as_str())
+        .collect();
+
+    let mut names: HashMap<String, String> = HashMap::new();
+    let mut components = Vec::new();
+    let mut root = None;
+    for package in packages {
+        if described.contains(&package.id.as_str()) {
+            // The software itself may be packaged for any ecosystem
+            let purl_name = package.purl.as_deref().and_then(parse_purl).map(|(_, name)| name);
+            if let Some(name) = purl_name.or(package.name).filter(|n| !n.is_empty()) {
+                root.get_or_insert((name, package.version));
+            }
+            continue;
+        }
+        let Some(name) = component_name(package.name.as_deref(), package.purl.as_deref()) else {
+            continue;
+        };
+        names.insert(package.id.clone(), name.clone());
+        components.push(ImportedComponent {
+            name,
+            version: package.version,
+            scope: "main",
+            span: package.span,
+        });
+    }
+
+    let edges = relationships
+        .iter()
+        .filter_map(|(subject, kind, object)| spdx_edge(subject, kind, object))
+        .filter_map(|(from, to)| Some((names.get(from)?.clone(), names.get(to)?.clone())))
+        .collect();
+
+    let (name, version) = root.unwrap_or_else(|| (document_name.unwrap_or_else(|| "sbom".to_string()), None));
+    ImportedSbom {
+        name,
+        version,
+        components,
+        edges,
+    }
+}
+
+fn spdx_json(document: &Value, content: &str) -> ImportedSbom {
+    let str_field = |value: &Value, key: &str| value.get(key).and_then(|v| v.as_str()).map(String::from);
+    let mut cursor = content.find("\"packages\"").unwrap_or(0);
+    let packages = document
+        .get("packages")
+        .and_then(|p| p.as_array())
+        .into_iter()
+        .flatten()
+        .map(|package| {
+            let id = str_field(package, "SPDXID").unwrap_or_default();
+            let purl = package
+                .get("externalRefs")
+                .and_then(|r| r.as_array())
+                .into_iter()
+                .flatten()
+                .find(|r| r.get("referenceType").and_then(|t| t.as_str()) == Some("purl"))
+                .and_then(|r| str_field(r, "referenceLocator"));
+            SpdxPackage {
+                span: json_span(content, &id, &mut cursor),
+                id,
+                name: str_field(package, "name"),
+                version: str_field(package, "versionInfo"),
+                purl,
+            }
+        })
+        .collect();
+
+    let mut relationships: Vec<(String, String, String)> = document
+        .get("relationships")
+        .and_then(|r| r.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|r| {
+            Some((
+                str_field(r, "spdxElementId")?,
+                str_field(r, "relationshipType")?,
+                str_field(r, "relatedSpdxElement")?,
+            ))
+        })
+        .collect();
+    // Older documents list described packages separately
+    for described in document
+        .get("documentDescribes")
+        .and_then(|d| d.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|d| d.as_str())
+    {
+        relationships.push((
+            "SPDXRef-DOCUMENT".to_string(),
+            "DESCRIBES".to_string(),
+            described.to_string(),
+        ));
+    }
+
+    spdx_document(packages, relationships, str_field(document, "name"))
+}
+
+fn spdx_tag_value(content: &str) -> ImportedSbom {
+    let mut packages: Vec<SpdxPackage> = Vec::new();
+    let mut relationships = Vec::new();
+    let mut document_name = None;
+
+    let mut offset = 0;
+    for raw_line in content.split_inclusive('\n') {
+        let line_start = offset;
+        offset += raw_line.len();
+        let Some((tag, value)) = raw_line.trim().split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match tag {
+            "DocumentName" => document_name = Some(value.to_string()),
+            "PackageName" => packages.push(SpdxPackage {
+                id: String::new(),
+                name: Some(value.to_string()),
+                version: None,
+                purl: None,
+                span: SourceSpan::from_offsets(content, line_start, line_start + raw_line.trim_end().len()),
+            }),
+            "SPDXID" => {
+                if let Some(package) = packages.last_mut().filter(|p| p.id.is_empty()) {
+                    package.id = value.to_string();
+                }
+            }
+            "PackageVersion" => {
+                if let Some(package) = packages.last_mut() {
+                    package.version = Some(value.to_string());
+                }
+            }
+            "ExternalRef" => {
+                let parts: Vec<&str> = value.split_whitespace().collect();
+                if let (Some(package), ["PACKAGE-MANAGER" | "PACKAGE_MANAGER", "purl", locator]) =
+                    (packages.last_mut(), parts.as_slice())
+                {
+                    package.purl = Some(locator.to_string());
+                }
+            }
+            "Relationship" => {
+                if let [subject, kind, object] = value.split_whitespace().collect::<Vec<_>>()[..] {
+                    relationships.push((subject.to_string(), kind.to_string(), object.to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    spdx_document(packages, relationships, document_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn purls_keep_their_type() {
+        assert_eq!(
+            parse_purl("pkg:PyPI/Foo_Bar@1.0?x=y"),
+            Some(("pypi".to_string(), "Foo_Bar".to_string()))
+        );
+        assert_eq!(
+            parse_purl("pkg:npm/%40types/node@20"),
+            Some(("npm".to_string(), "node".to_string()))
+        );
+        assert_eq!(component_name(Some("left-pad"), Some("pkg:npm/left-pad@1.3.0")), None);
+        assert_eq!(component_name(Some("numpy"), Some("pkg:conda/numpy@1.26")), None);
+        assert_eq!(component_name(Some(" Flask "), None), Some("Flask".to_string()));
+    }
+
+    #[test]
+    fn components_of_other_ecosystems_are_left_out() {
+        let content = r#"{
+  "bomFormat": "CycloneDX",
+  "metadata": { "component": { "name": "app", "version": "1.0" } },
+  "components": [
+    { "bom-ref": "a", "name": "requests", "purl": "pkg:pypi/requests@2.31.0" },
+    { "bom-ref": "b", "name": "left-pad", "purl": "pkg:npm/left-pad@1.3.0" },
+    { "bom-ref": "c", "name": "urllib3", "version": "2.0" }
+  ],
+  "dependencies": [{ "ref": "a", "dependsOn": ["b", "c"] }]
+}"#;
+        let document: Value = serde_json::from_str(content).unwrap();
+        let sbom = cyclonedx_json(&document, content);
+        let names: Vec<&str> = sbom.components.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["requests", "urllib3"]);
+        assert_eq!(sbom.edges, [("requests".to_string(), "urllib3".to_string())]);
+    }
+
+    #[test]
+    fn spdx_root_may_be_any_package_type() {
+        let content = "\
+SPDXVersion: SPDX-2.3
+DocumentName: vendor
+PackageName: service
+SPDXID: SPDXRef-service
+ExternalRef: PACKAGE-MANAGER purl pkg:docker/acme/service@3.1
+PackageName: numpy
+SPDXID: SPDXRef-numpy
+ExternalRef: PACKAGE-MANAGER purl pkg:pypi/numpy@1.26.4
+Relationship: SPDXRef-DOCUMENT DESCRIBES SPDXRef-service
+Relationship: SPDXRef-service DEPENDS_ON SPDXRef-numpy
+";
+        let sbom = spdx_tag_value(content);
+        assert_eq!(sbom.name, "service");
+        let names: Vec<&str> = sbom.components.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["numpy"]);
+    }
+}
@@ -0,0 +1,366 @@
+//! Minimal PEP 440 version specifiers: enough to intersect the ranges
+//! declared for a package in different places.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A release version such as `1.19.2`. Pre/post/dev suffixes are dropped, so
+/// `2.0rc1` compares equal to `2.0`.
+#[derive(Debug, Clone)]
+pub struct PyVersion {
+    release: Vec<u64>,
+}
+
+impl PyVersion {
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.trim().trim_start_matches(['v', 'V']);
+        let version = version.split('+').next()?;
+        let mut release = Vec::new();
+        for part in version.split('.') {
+            let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+            if digits.is_empty() {
+                break;
+            }
+            release.push(digits.parse().ok()?);
+            if digits.len() != part.len() {
+                // Suffix such as rc1 or post2 ends the release segment
+                break;
+            }
+        }
+        if release.is_empty() {
+            None
+        } else {
+            Some(Self { release })
+        }
+    }
+
+    /// The smallest version above every version starting with this prefix
+    /// of `len` components, e.g. 1.4 -> 1.5.
+    fn bump(&self, len: usize) -> Self {
+        let mut release: Vec<u64> = self.release.iter().copied().take(len).collect();
+        release.resize(len.max(1), 0);
+        if let Some(last) = release.last_mut() {
+            *last += 1;
+        }
+        Self { release }
+    }
+}
+
+impl PartialEq for PyVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PyVersion {}
+
+impl PartialOrd for PyVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PyVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.release.len().max(other.release.len());
+        (0..len)
+            .map(|i| {
+                let a = self.release.get(i).unwrap_or(&0);
+                let b = other.release.get(i).unwrap_or(&0);
+                a.cmp(b)
+            })
+            .find(|o| o.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl fmt::Display for PyVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self.release.iter().map(|p| p.to_string()).collect();
+        write!(f, "{}", parts.join("."))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Bound {
+    version: PyVersion,
+    inclusive: bool,
+}
+
+/// The set of versions allowed by one or more specifiers.
+#[derive(Debug, Clone, Default)]
+pub struct VersionRange {
+    lower: Option<Bound>,
+    upper: Option<Bound>,
+    excluded: Vec<PyVersion>,
+}
+
+impl VersionRange {
+    /// Parse a specifier set such as `>=1.18,<2`, `~=1.4.5` or `==1.2.*`.
+    /// A leading package name (`numpy>=1.18`) is skipped. Returns `None` if
+    /// any clause is not understood, e.g. URLs or arbitrary equality.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let spec = match spec.find(['=', '>', '<', '~', '!']) {
+            Some(idx) => &spec[idx..],
+            None if spec.trim().is_empty() || spec.trim() == "*" => "",
+            // A bare package name allows any version
+            None if !spec.contains(['/', ':', ' ']) => "",
+            None => return None,
+        };
+
+        let mut range = Self::default();
+        for clause in spec.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+            let op_len = clause
+                .find(|c: char| !matches!(c, '=' | '>' | '<' | '~' | '!'))
+                .unwrap_or(clause.len());
+            let (op, version) = clause.split_at(op_len);
+            let version = version.trim();
+            let (version_str, wildcard) = match version.strip_suffix(".*") {
+                Some(prefix) => (prefix, true),
+                None => (version, false),
+            };
+            let parsed = PyVersion::parse(version_str)?;
+            let prefix_len = parsed.release.len();
+
+            match (op, wildcard) {
+                (">=", false) => range.raise_lower(parsed, true),
+                (">", false) => range.raise_lower(parsed, false),
+                ("<=", false) => range.lower_upper(parsed, true),
+                ("<", false) => range.lower_upper(parsed, false),
+                ("==", false) | ("=", false) => {
+                    range.raise_lower(parsed.clone(), true);
+                    range.lower_upper(parsed, true);
+                }
+                ("==", true) | ("=", true) => {
+                    range.lower_upper(parsed.bump(prefix_len), false);
+                    range.raise_lower(parsed, true);
+                }
+                ("!=", false) => range.excluded.push(parsed),
+                ("~=", false) if prefix_len >= 2 => {
+                    range.lower_upper(parsed.bump(prefix_len - 1), false);
+                    range.raise_lower(parsed, true);
+                }
+                // != with a wildcard, ~= on a single component and === are
+                // not representable here
+                _ => return None,
+            }
+        }
+        Some(range)
+    }
+
+    fn raise_lower(&mut self, version: PyVersion, inclusive: bool) {
+        let replace = match &self.lower {
+            None => true,
+            Some(bound) => match version.cmp(&bound.version) {
+                Ordering::Greater => true,
+                Ordering::Equal => bound.inclusive && !inclusive,
+                Ordering::Less => false,
+            },
+        };
+        if replace {
+            self.lower = Some(Bound { version, inclusive });
+        }
+    }
+
+    fn lower_upper(&mut self, version: PyVersion, inclusive: bool) {
+        let replace = match &self.upper {
+            None => true,
+            Some(bound) => match version.cmp(&bound.version) {
+                Ordering::Less => true,
+                Ordering::Equal => bound.inclusive && !inclusive,
+                Ordering::Greater => false,
+            },
+        };
+        if replace {
+            self.upper = Some(Bound { version, inclusive });
+        }
+    }
+
+    /// Versions allowed by both ranges.
+    pub fn intersect(&self, other: &VersionRange) -> VersionRange {
+        let mut range = self.clone();
+        if let Some(bound) = &other.lower {
+            range.raise_lower(bound.version.clone(), bound.inclusive);
+        }
+        if let Some(bound) = &other.upper {
+            range.lower_upper(bound.version.clone(), bound.inclusive);
+        }
+        range.excluded.extend(other.excluded.iter().cloned());
+        range
+    }
+
+    /// Whether no version satisfies the range.
+    pub fn is_empty(&self) -> bool {
+        match (&self.lower, &self.upper) {
+            (Some(lower), Some(upper)) => match lower.version.cmp(&upper.version) {
+                Ordering::Greater => true,
+                Ordering::Equal => {
+                    !(lower.inclusive && upper.inclusive)
+                        || self.excluded.contains(&lower.version)
+                }
+                Ordering::Less => false,
+            },
+            _ => false,
+        }
+    }
+
+    pub fn contains(&self, version: &PyVersion) -> bool {
+        let above = self.lower.as_ref().is_none_or(|b| match version.cmp(&b.version) {
+            Ordering::Greater => true,
+            Ordering::Equal => b.inclusive,
+            Ordering::Less => false,
+        });
+        let below = self.upper.as_ref().is_none_or(|b| match version.cmp(&b.version) {
+            Ordering::Less => true,
+            Ordering::Equal => b.inclusive,
+            Ordering::Greater => false,
+        });
+        above && below && !self.excluded.contains(version)
+    }
+
+    /// Whether `version` lies within the bounds, ignoring exclusions.
+    fn contains_bounds(&self, version: &PyVersion) -> bool {
+        let bounds = VersionRange {
+            lower: self.lower.clone(),
+            upper: self.upper.clone(),
+            excluded: Vec::new(),
+        };
+        bounds.contains(version)
+    }
+}
+
+impl fmt::Display for VersionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "<none>");
+        }
+        let mut clauses = Vec::new();
+        match (&self.lower, &self.upper) {
+            (Some(lower), Some(upper))
+                if lower.version == upper.version && lower.inclusive && upper.inclusive =>
+            {
+                clauses.push(format!("=={}", lower.version));
+            }
+            _ => {
+                if let Some(lower) = &self.lower {
+                    let op = if lower.inclusive { ">=" } else { ">" };
+                    clauses.push(format!("{}{}", op, lower.version));
+                }
+                if let Some(upper) = &self.upper {
+                    let op = if upper.inclusive { "<=" } else { "<" };
+                    clauses.push(format!("{}{}", op, upper.version));
+                }
+            }
+        }
+        let mut excluded: Vec<&PyVersion> =
+            self.excluded.iter().filter(|v| self.contains_bounds(v)).collect();
+        excluded.sort();
+        excluded.dedup();
+        clauses.extend(excluded.into_iter().map(|v| format!("!={}", v)));
+
+        if clauses.is_empty() {
+            write!(f, "*")
+        } else {
+            write!(f, "{}", clauses.join(","))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(spec: &str) -> VersionRange {
+        VersionRange::parse(spec).unwrap_or_else(|| panic!("'{}' should parse", spec))
+    }
+
+    fn version(version: &str) -> PyVersion {
+        PyVersion::parse(version).unwrap()
+    }
+
+    #[test]
+    fn intersect_keeps_the_tightest_bounds() {
+        let merged = range(">=1.10").intersect(&range(">=1.18,<2")).intersect(&range("<=1.26"));
+        assert_eq!(merged.to_string(), ">=1.18,<=1.26");
+        assert!(!merged.is_empty());
+    }
+
+    #[test]
+    fn intersect_prefers_exclusive_bounds_at_the_same_version() {
+        assert_eq!(range(">=1.0").intersect(&range(">1.0")).to_string(), ">1.0");
+        assert_eq!(range("<2").intersect(&range("<=2")).to_string(), "<2");
+    }
+
+    #[test]
+    fn intersect_carries_exclusions_inside_the_bounds() {
+        let merged = range(">=1,<2,!=1.5").intersect(&range("!=3"));
+        assert_eq!(merged.to_string(), ">=1,<2,!=1.5");
+    }
+
+    #[test]
+    fn disjoint_ranges_are_empty() {
+        let merged = range(">=2").intersect(&range("<1.5"));
+        assert!(merged.is_empty());
+        assert_eq!(merged.to_string(), "<none>");
+    }
+
+    #[test]
+    fn touching_bounds_are_empty_unless_both_inclusive() {
+        assert!(range(">=1.0,<1.0").is_empty());
+        assert!(!range(">=1.0,<=1.0").is_empty());
+        assert!(range("==1.0,!=1.0").is_empty());
+    }
+
+    #[test]
+    fn pinned_version_displays_as_equality() {
+        assert_eq!(range("==1.4.2").to_string(), "==1.4.2");
+        assert_eq!(range("==1.4.2").intersect(&range(">=1")).to_string(), "==1.4.2");
+    }
+
+    #[test]
+    fn bump_increments_the_last_kept_component() {
+        assert_eq!(version("1.4.5").bump(2).to_string(), "1.5");
+        assert_eq!(version("1.4.5").bump(3).to_string(), "1.4.6");
+        assert_eq!(version("1").bump(2).to_string(), "1.1");
+    }
+
+    #[test]
+    fn compatible_release_caps_the_second_to_last_component() {
+        assert_eq!(range("~=1.4.5").to_string(), ">=1.4.5,<1.5");
+        assert_eq!(range("~=2.2").to_string(), ">=2.2,<3");
+        assert!(range("~=1.4.5").contains(&version("1.4.9")));
+        assert!(!range("~=1.4.5").contains(&version("1.5")));
+        // A single component has no compatible release
+        assert!(VersionRange::parse("~=1").is_none());
+    }
+
+    #[test]
+    fn wildcard_equality_covers_the_prefix() {
+        assert_eq!(range("==1.5.*").to_string(), ">=1.5,<1.6");
+        assert!(range("==1.5.*").contains(&version("1.5.3")));
+        assert!(!range("==1.5.*").contains(&version("1.6")));
+        assert!(range("==1.5.*").intersect(&range("~=1.4.2")).is_empty());
+    }
+
+    #[test]
+    fn pre_releases_compare_as_their_release() {
+        assert_eq!(version("2.0rc1"), version("2.0"));
+        assert_eq!(version("1.0.post2"), version("1.0"));
+        assert_eq!(range(">=2.0rc1").to_string(), ">=2.0");
+        assert!(range(">=2.0b1,<3").contains(&version("2.0")));
+    }
+
+    #[test]
+    fn package_names_and_wildcards_parse() {
+        assert_eq!(range("numpy>=1.18").to_string(), ">=1.18");
+        assert_eq!(range("numpy").to_string(), "*");
+        assert_eq!(range("*").to_string(), "*");
+    }
+
+    #[test]
+    fn unsupported_specs_do_not_parse() {
+        assert!(VersionRange::parse("===1.0").is_none());
+        assert!(VersionRange::parse("!=1.*").is_none());
+        assert!(VersionRange::parse("foo @ https://example.org/foo.zip").is_none());
+    }
+}
@@ -0,0 +1,194 @@
+//! Native SVG rendering of the dependency graph, using the same clusters
+//! and colors as the DOT output but without needing Graphviz.
+
+use std::fmt::Write;
+
+use crate::layout::{self, LayoutNode};
+use crate::DependencyAnalyzer;
+
+const FONT_SIZE: f64 = 12.0;
+/// Rough advance width of an Arial character at `FONT_SIZE`.
+const CHAR_WIDTH: f64 = 7.0;
+const LINE_HEIGHT: f64 = 15.0;
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A smooth path through `points` with horizontal tangents at each point.
+pub fn edge_path(points: &[(f64, f64)]) -> String {
+    let mut path = String::new();
+    if let Some(&(x, y)) = points.first() {
+        let _ = write!(path, "M{:.1},{:.1}", x, y);
+    }
+    for pair in points.windows(2) {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        let dx = (x1 - x0) / 2.0;
+        let _ = write!(
+            path,
+            " C{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}",
+            x0 + dx,
+            y0,
+            x1 - dx,
+            y1,
+            x1,
+            y1
+        );
+    }
+    path
+}
+
+/// Render the graph of `analyzer` as a standalone SVG document.
+pub fn render(analyzer: &DependencyAnalyzer) -> String {
+    let clusters = analyzer.graph_clusters();
+    let graph = &analyzer.graph;
+
+    // Layout order follows the clusters so band indices match them
+    let mut order = Vec::new();
+    let mut nodes = Vec::new();
+    let mut styles = Vec::new();
+    let mut slot = vec![0usize; graph.node_count()];
+    for (band, (_, _, members)) in clusters.iter().enumerate() {
+        for &node in members {
+            let (color, label) = analyzer.node_style(&graph[node]);
+            let lines: Vec<String> = label.lines().map(String::from).collect();
+            let longest = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+            slot[node.index()] = order.len();
+            order.push(node);
+            nodes.push(LayoutNode {
+                width: longest as f64 * CHAR_WIDTH + 24.0,
+                height: lines.len() as f64 * LINE_HEIGHT + 14.0,
+                band,
+            });
+            styles.push((color, lines));
+        }
+    }
+
+    let cycles = analyzer.cycle_membership();
+    let mut edges = Vec::new();
+    let mut edge_info = Vec::new();
+    let mut edge_list: Vec<_> = graph
+        .edge_indices()
+        .filter_map(|e| graph.edge_endpoints(e))
+        .collect();
+    edge_list.sort_by_key(|&(from, to)| (slot[from.index()], slot[to.index()]));
+    for (from, to) in edge_list {
+        edges.push((slot[from.index()], slot[to.index()]));
+        edge_info.push((
+            from,
+            to,
+            analyzer.is_conflict_edge(from, to),
+            cycles.has_edge(from, to),
+        ));
+    }
+
+    let result = layout::layout(&nodes, &edges);
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.0}\" height=\"{h:.0}\" viewBox=\"0 0 {w:.0} {h:.0}\" font-family=\"Arial, Helvetica, sans-serif\">",
+        w = result.width,
+        h = result.height
+    );
+    svg.push_str("  <defs>\n");
+    for (id, color) in [
+        ("arrow", "#666666"),
+        ("arrow-conflict", "#CC0000"),
+        ("arrow-cycle", "#8E44AD"),
+    ] {
+        let _ = writeln!(
+            svg,
+            "    <marker id=\"{}\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"8\" markerHeight=\"8\" orient=\"auto-start-reverse\"><path d=\"M0,0 L10,5 L0,10 z\" fill=\"{}\"/></marker>",
+            id, color
+        );
+    }
+    svg.push_str("  </defs>\n");
+    svg.push_str("  <rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n");
+
+    for (band, (cluster_id, label, _)) in clusters.iter().enumerate() {
+        let Some(area) = result.bands.get(&band) else {
+            continue;
+        };
+        let _ = writeln!(
+            svg,
+            "  <g class=\"cluster\" id=\"{}\">\n    <rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" rx=\"8\" fill=\"{}\" stroke=\"#E0E0E0\"/>\n    <text x=\"{:.1}\" y=\"{:.1}\" font-size=\"{}\" text-anchor=\"middle\" fill=\"#2D3436\">{}</text>\n  </g>",
+            escape(cluster_id),
+            area.x,
+            area.y,
+            area.width,
+            area.height,
+            analyzer.category_color(label).unwrap_or("#F8F8F8"),
+            area.x + area.width / 2.0,
+            area.y + 17.0,
+            FONT_SIZE + 1.0,
+            escape(label)
+        );
+    }
+
+    for (points, &(from, to, conflict, cycle)) in result.edges.iter().zip(&edge_info) {
+        if points.len() < 2 {
+            continue;
+        }
+        let (stroke, extra, marker) = if conflict {
+            ("#CC0000", " stroke-width=\"2\" stroke-dasharray=\"6,4\"", "arrow-conflict")
+        } else if cycle {
+            ("#8E44AD", " stroke-width=\"2\"", "arrow-cycle")
+        } else {
+            ("#666666", "", "arrow")
+        };
+        let _ = writeln!(
+            svg,
+            "  <path class=\"edge{}\" data-from=\"{}\" data-to=\"{}\" d=\"{}\" fill=\"none\" stroke=\"{}\"{} marker-end=\"url(#{})\"/>",
+            if cycle { " cycle" } else { "" },
+            escape(&graph[from]),
+            escape(&graph[to]),
+            edge_path(points),
+            stroke,
+            extra,
+            marker
+        );
+    }
+
+    for (i, node) in order.iter().enumerate() {
+        let place = result.nodes[i];
+        let size = &nodes[i];
+        let (color, lines) = &styles[i];
+        // Packages on a cycle get a thicker outline
+        let cycle = cycles.contains(*node);
+        let _ = writeln!(
+            svg,
+            "  <g class=\"node{}\" id=\"n{}\" data-package=\"{}\">\n    <title>{}</title>\n    <rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" rx=\"6\" fill=\"white\" stroke=\"{}\" stroke-width=\"{}\"/>",
+            if cycle { " cycle" } else { "" },
+            node.index(),
+            escape(&graph[*node]),
+            escape(&graph[*node]),
+            place.x,
+            place.y,
+            size.width,
+            size.height,
+            color,
+            if cycle { "3" } else { "1.5" }
+        );
+        let first_baseline =
+            place.y + size.height / 2.0 - (lines.len() as f64 - 1.0) * LINE_HEIGHT / 2.0 + 4.0;
+        for (line_no, line) in lines.iter().enumerate() {
+            let _ = writeln!(
+                svg,
+                "    <text x=\"{:.1}\" y=\"{:.1}\" font-size=\"{}\" text-anchor=\"middle\" fill=\"{}\">{}</text>",
+                place.x + size.width / 2.0,
+                first_baseline + line_no as f64 * LINE_HEIGHT,
+                if line_no == 0 { FONT_SIZE } else { FONT_SIZE - 2.0 },
+                color,
+                escape(line)
+            );
+        }
+        svg.push_str("  </g>\n");
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
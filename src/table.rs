@@ -0,0 +1,47 @@
+//! Plain-text and Markdown tables for the text reports.
+
+/// Columns padded to their widest cell, with a dashed rule under the header.
+pub fn render_text(header: &[String], rows: &[Vec<String>]) -> String {
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| {
+            rows.iter()
+                .map(|r| r[i].chars().count())
+                .chain(std::iter::once(header[i].chars().count()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let format_row = |cells: &[String]| -> String {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        format!("{}\n", padded.join("  ").trim_end())
+    };
+
+    let mut output = format_row(header);
+    let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+    output.push_str(&format_row(&rule));
+    for row in rows {
+        output.push_str(&format_row(row));
+    }
+    output
+}
+
+pub fn render_markdown(header: &[String], rows: &[Vec<String>]) -> String {
+    let escape = |cell: &String| cell.replace('|', "\\|");
+    let mut output = format!(
+        "| {} |\n",
+        header.iter().map(escape).collect::<Vec<_>>().join(" | ")
+    );
+    output.push_str(&format!("|{}\n", " --- |".repeat(header.len())));
+    for row in rows {
+        output.push_str(&format!(
+            "| {} |\n",
+            row.iter().map(escape).collect::<Vec<_>>().join(" | ")
+        ));
+    }
+    output
+}
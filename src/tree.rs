@@ -0,0 +1,338 @@
+//! Tree view of the dependency graph for `--format text`, in the spirit of
+//! `cargo tree` and `pipdeptree`. Projects are the roots; their requirements
+//! and the graph edges between packages form the branches.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::Write;
+
+use crate::lockfile::normalize_name;
+use crate::{relative_path, Dependency, DependencyAnalyzer};
+
+/// Projects and packages joined by the requirements in project manifests
+/// and by the edges of the dependency graph.
+pub struct RequirementGraph<'a> {
+    /// Every package and project name
+    pub nodes: BTreeSet<String>,
+    /// (dependent, dependency) pairs
+    pub edges: BTreeSet<(String, String)>,
+    /// Requirement records behind the edges declared in a manifest, in
+    /// manifest order
+    pub requirements: BTreeMap<(String, String), Vec<&'a Dependency>>,
+}
+
+impl<'a> RequirementGraph<'a> {
+    pub fn build(analyzer: &'a DependencyAnalyzer) -> Self {
+        let project_names: BTreeMap<&str, &str> = analyzer
+            .projects
+            .iter()
+            .map(|p| (p.id.as_str(), p.name.as_str()))
+            .collect();
+
+        let mut edges: BTreeSet<(String, String)> = analyzer
+            .graph
+            .edge_indices()
+            .filter_map(|e| analyzer.graph.edge_endpoints(e))
+            .map(|(from, to)| (analyzer.graph[from].clone(), analyzer.graph[to].clone()))
+            .collect();
+        let mut requirements: BTreeMap<(String, String), Vec<&Dependency>> = BTreeMap::new();
+        for dep in analyzer.dependencies.values().flatten() {
+            let Some(&project) = project_names.get(dep.project.as_str()) else {
+                continue;
+            };
+            // Requirements on a collapsed package point at its cluster
+            let name = analyzer.collapsed.get(&dep.name).unwrap_or(&dep.name);
+            if project == name {
+                continue;
+            }
+            let pair = (project.to_string(), name.clone());
+            edges.insert(pair.clone());
+            requirements.entry(pair).or_default().push(dep);
+        }
+        for deps in requirements.values_mut() {
+            deps.sort_by(|a, b| (&a.source_file, a.span.line).cmp(&(&b.source_file, b.span.line)));
+        }
+
+        let mut nodes: BTreeSet<String> = analyzer.graph.node_weights().cloned().collect();
+        nodes.extend(project_names.values().map(|name| name.to_string()));
+        Self {
+            nodes,
+            edges,
+            requirements,
+        }
+    }
+}
+
+/// Marker for a package whose subtree was already printed.
+const SEEN: &str = " (*)";
+
+struct Tree<'a> {
+    analyzer: &'a DependencyAnalyzer,
+    /// Branches to print below each node
+    children: BTreeMap<String, BTreeSet<String>>,
+    /// Requirement records behind each (dependent, dependency) pair
+    requirements: BTreeMap<(String, String), Vec<&'a Dependency>>,
+    /// Project label by name, for root lines
+    projects: BTreeMap<String, String>,
+    invert: bool,
+    max_depth: Option<usize>,
+    /// Packages whose children were printed
+    expanded: HashSet<String>,
+    output: String,
+}
+
+/// Render the graph of `analyzer` as a tree. With `invert`, every package
+/// lists what depends on it instead of what it depends on. `max_depth`
+/// limits how many levels below the roots are shown.
+pub fn render(analyzer: &DependencyAnalyzer, max_depth: Option<usize>, invert: bool) -> String {
+    let RequirementGraph {
+        nodes,
+        edges,
+        requirements,
+    } = RequirementGraph::build(analyzer);
+
+    let mut children: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let mut has_parent: HashSet<String> = HashSet::new();
+    for (dependent, dependency) in &edges {
+        let (parent, child) = if invert {
+            (dependency, dependent)
+        } else {
+            (dependent, dependency)
+        };
+        children.entry(parent.clone()).or_default().insert(child.clone());
+        has_parent.insert(child.clone());
+    }
+
+    let projects = analyzer
+        .projects
+        .iter()
+        .map(|p| {
+            let mut label = p.name.clone();
+            if let Some(version) = &p.version {
+                let _ = write!(label, " v{}", version);
+            }
+            let _ = write!(label, " ({})", p.id);
+            (p.name.clone(), label)
+        })
+        .collect();
+
+    let mut tree = Tree {
+        analyzer,
+        children,
+        requirements,
+        projects,
+        invert,
+        max_depth,
+        expanded: HashSet::new(),
+        output: String::new(),
+    };
+
+    // Roots first; anything they cannot reach sits on a cycle and starts a
+    // tree of its own
+    let mut reachable = HashSet::new();
+    let roots: Vec<&String> = nodes.iter().filter(|n| !has_parent.contains(*n)).collect();
+    for root in roots {
+        tree.mark_reachable(root, &mut reachable);
+        tree.print_root(root);
+    }
+    for node in &nodes {
+        if !reachable.contains(node) {
+            tree.mark_reachable(node, &mut reachable);
+            tree.print_root(node);
+        }
+    }
+    tree.output
+}
+
+impl Tree<'_> {
+    fn mark_reachable(&self, start: &str, reachable: &mut HashSet<String>) {
+        let mut stack = vec![start.to_string()];
+        while let Some(name) = stack.pop() {
+            if let Some(children) = self.children.get(&name) {
+                stack.extend(children.iter().filter(|c| !reachable.contains(*c)).cloned());
+            }
+            reachable.insert(name);
+        }
+    }
+
+    fn print_root(&mut self, name: &str) {
+        if !self.output.is_empty() {
+            self.output.push('\n');
+        }
+        let label = self
+            .projects
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_string());
+        self.output.push_str(&label);
+        self.expand(name, "", 0);
+    }
+
+    /// Print the children of `name`, which is at `depth`, below `prefix`.
+    fn expand(&mut self, name: &str, prefix: &str, depth: usize) {
+        let children = self.children.get(name).cloned().unwrap_or_default();
+        let seen = self.expanded.contains(name);
+        if seen && !children.is_empty() {
+            self.output.push_str(SEEN);
+        }
+        self.output.push('\n');
+        if seen || children.is_empty() || self.max_depth.is_some_and(|max| depth >= max) {
+            return;
+        }
+        self.expanded.insert(name.to_string());
+
+        let count = children.len();
+        for (i, child) in children.iter().enumerate() {
+            let last = i + 1 == count;
+            let pair = if self.invert {
+                (child.clone(), name.to_string())
+            } else {
+                (name.to_string(), child.clone())
+            };
+            let _ = write!(
+                self.output,
+                "{}{}{}",
+                prefix,
+                if last { "└── " } else { "├── " },
+                self.label(child, &pair)
+            );
+            let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+            self.expand(child, &child_prefix, depth + 1);
+        }
+    }
+
+    /// `name (specs) <scopes> [from manifests]` for a requirement, or just
+    /// the name for edges the manifests do not spell out.
+    fn label(&self, name: &str, pair: &(String, String)) -> String {
+        let Some(deps) = self.requirements.get(pair) else {
+            return name.to_string();
+        };
+        let mut specs: Vec<&str> = Vec::new();
+        let mut scopes: Vec<&str> = Vec::new();
+        let mut sources: Vec<String> = Vec::new();
+        // A collapsed cluster lists the packages it was required as instead
+        let collapsed = !self.analyzer.collapsed_members(name).is_empty();
+        for dep in deps {
+            let spec = if collapsed { dep.name.as_str() } else { display_spec(dep) };
+            if !spec.is_empty() && !specs.contains(&spec) {
+                specs.push(spec);
+            }
+            if dep.scope != "main" && !scopes.contains(&dep.scope.as_str()) {
+                scopes.push(&dep.scope);
+            }
+            let mut source = relative_path(&dep.source_file, &self.analyzer.root);
+            if dep.span.is_known() {
+                let _ = write!(source, ":{}", dep.span.line);
+            }
+            sources.push(source);
+        }
+
+        let mut label = name.to_string();
+        if !specs.is_empty() {
+            let _ = write!(label, " ({})", specs.join(" | "));
+        }
+        for scope in scopes {
+            let _ = write!(label, " <{}>", scope);
+        }
+        let _ = write!(label, " [from {}]", sources.join(", "));
+        label
+    }
+}
+
+/// The version part of a requirement; some manifests repeat the name.
+pub fn display_spec(dep: &Dependency) -> &str {
+    strip_name(&dep.name, &dep.version_spec)
+}
+
+/// `spec` without a leading package `name`, however the name is spelled
+/// there (`Foo_Bar>=1` for `foo-bar`).
+pub fn strip_name<'a>(name: &str, spec: &'a str) -> &'a str {
+    let spec = spec.trim();
+    let end = spec
+        .find(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .unwrap_or(spec.len());
+    if end > 0 && normalize_name(&spec[..end]) == normalize_name(name) {
+        spec[end..].trim()
+    } else {
+        spec
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::Project;
+    use crate::{Config, SourceSpan};
+    use std::path::{Path, PathBuf};
+
+    /// Project `app` requiring `web>=1` and `http` (dev), with web -> http
+    /// and http -> urllib3 in the graph.
+    fn analyzer() -> DependencyAnalyzer {
+        let mut analyzer = DependencyAnalyzer::new(Config::default());
+        analyzer.root = PathBuf::from("/repo");
+        analyzer.projects.push(Project {
+            id: ".".to_string(),
+            name: "app".to_string(),
+            root: PathBuf::from("/repo"),
+            version: Some("1.0".to_string()),
+            license: None,
+        });
+        let manifest = Path::new("/repo/requirements.txt");
+        let content = "web>=1\nhttp\n";
+        let span = |start, end| SourceSpan::from_offsets(content, start, end);
+        analyzer.add_requirement("web>=1", manifest, span(0, 6), "main");
+        analyzer.add_requirement("http", manifest, span(7, 11), "dev");
+        for (from, to) in [("web", "http"), ("http", "urllib3")] {
+            let (from, to) = (analyzer.ensure_node(from), analyzer.ensure_node(to));
+            analyzer.graph.add_edge(from, to, ());
+        }
+        analyzer
+    }
+
+    #[test]
+    fn repeated_subtrees_are_marked_instead_of_printed_again() {
+        assert_eq!(
+            render(&analyzer(), None, false),
+            "\
+app v1.0 (.)
+├── http <dev> [from requirements.txt:2]
+│   └── urllib3
+└── web (>=1) [from requirements.txt:1]
+    └── http (*)
+"
+        );
+    }
+
+    #[test]
+    fn inverted_tree_starts_from_the_leaves() {
+        assert_eq!(
+            render(&analyzer(), None, true),
+            "\
+urllib3
+└── http
+    ├── app <dev> [from requirements.txt:2]
+    └── web
+        └── app (>=1) [from requirements.txt:1]
+"
+        );
+    }
+
+    #[test]
+    fn depth_limits_the_levels_below_the_roots() {
+        assert_eq!(
+            render(&analyzer(), Some(1), false),
+            "\
+app v1.0 (.)
+├── http <dev> [from requirements.txt:2]
+└── web (>=1) [from requirements.txt:1]
+"
+        );
+        assert_eq!(render(&analyzer(), Some(0), true), "urllib3\n");
+    }
+
+    #[test]
+    fn names_are_stripped_from_specs_however_spelled() {
+        assert_eq!(strip_name("foo-bar", "Foo_Bar>=1"), ">=1");
+        assert_eq!(strip_name("foo", "foobar>=1"), "foobar>=1");
+        assert_eq!(strip_name("numpy", " numpy "), "");
+    }
+}
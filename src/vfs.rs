@@ -0,0 +1,96 @@
+//! Where the scan reads manifests and lockfiles from: the working tree, or
+//! a git revision read straight from the object database.
+
+use anyhow::{Context, Result};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::git;
+use crate::project::normalize_path;
+
+pub enum FileSystem {
+    Disk,
+    Git(GitTree),
+}
+
+impl FileSystem {
+    pub fn read_to_string(&self, path: &Path) -> Result<String> {
+        match self {
+            FileSystem::Disk => Ok(fs::read_to_string(path)?),
+            FileSystem::Git(tree) => tree.read_to_string(path),
+        }
+    }
+
+    pub fn is_file(&self, path: &Path) -> bool {
+        match self {
+            FileSystem::Disk => path.is_file(),
+            FileSystem::Git(tree) => tree.blob(path).is_some(),
+        }
+    }
+}
+
+/// The files below a directory of a git repository at one commit. Paths
+/// are addressed as if the commit was checked out in that directory.
+pub struct GitTree {
+    root: PathBuf,
+    commit: String,
+    /// Blob id of every regular file, by path relative to `root`
+    files: BTreeMap<PathBuf, String>,
+    /// Started on the first read
+    reader: RefCell<Option<git::BlobReader>>,
+}
+
+impl GitTree {
+    /// List the files below `root` at `revision` of the repository
+    /// containing `root`.
+    pub fn open(root: &Path, revision: &str) -> Result<Self> {
+        let commit = git::resolve(root, revision)?;
+        // Without --full-tree, ls-tree lists the current directory only, with
+        // paths relative to it
+        let listing = git::run(root, &["ls-tree", "-r", "-z", &commit])?;
+        let mut files = BTreeMap::new();
+        for entry in listing.split('\0').filter(|e| !e.is_empty()) {
+            let Some((info, path)) = entry.split_once('\t') else {
+                continue;
+            };
+            // Symlinks (120000) and submodules (commit entries) have no content
+            if let ["100644" | "100755", "blob", id] = info.split(' ').collect::<Vec<_>>()[..] {
+                files.insert(PathBuf::from(path), id.to_string());
+            }
+        }
+        Ok(Self {
+            root: root.to_path_buf(),
+            commit,
+            files,
+            reader: RefCell::new(None),
+        })
+    }
+
+    pub fn commit(&self) -> &str {
+        &self.commit
+    }
+
+    /// Paths of all files, relative to the root, sorted.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path)
+    }
+
+    fn blob(&self, path: &Path) -> Option<&String> {
+        let path = normalize_path(path);
+        let relative = path.strip_prefix(normalize_path(&self.root)).ok()?;
+        self.files.get(relative)
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String> {
+        let id = self.blob(path).with_context(|| {
+            format!("{} does not exist at commit {}", path.display(), &self.commit[..7])
+        })?;
+        let mut reader = self.reader.borrow_mut();
+        if reader.is_none() {
+            *reader = Some(git::BlobReader::open(&self.root)?);
+        }
+        reader.as_mut().expect("reader was just opened").read(id)
+    }
+}
@@ -0,0 +1,177 @@
+//! Graph views for large projects: the part of the graph around one
+//! package, a depth limit, category and scope filters, hidden leaves and
+//! collapsed clusters. The view is applied to the analyzer after the checks
+//! ran and before rendering, so every output format shows the same graph.
+
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::config::GraphConfig;
+use crate::lockfile::normalize_name;
+use crate::tree::RequirementGraph;
+use crate::DependencyAnalyzer;
+
+/// Cut the graph, the projects and the dependency records of `analyzer`
+/// down to `view`.
+pub fn apply(analyzer: &mut DependencyAnalyzer, view: &GraphConfig) -> Result<()> {
+    if !view.is_active() {
+        return Ok(());
+    }
+
+    if !view.scopes.is_empty() {
+        for deps in analyzer.dependencies.values_mut() {
+            deps.retain(|dep| view.scopes.iter().any(|scope| scope_matches(scope, &dep.scope)));
+        }
+        analyzer.dependencies.retain(|_, deps| !deps.is_empty());
+    }
+
+    let keep = {
+        let graph = RequirementGraph::build(analyzer);
+        let mut forward: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        let mut backward: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (dependent, dependency) in &graph.edges {
+            forward.entry(dependent).or_default().push(dependency);
+            backward.entry(dependency).or_default().push(dependent);
+        }
+        let resolve = |package: &str| {
+            let wanted = normalize_name(package);
+            graph
+                .nodes
+                .iter()
+                .find(|n| normalize_name(n) == wanted)
+                .map(String::as_str)
+                .ok_or_else(|| anyhow::anyhow!("Package '{}' is not in the dependency graph", package))
+        };
+
+        let mut keep = graph.nodes.clone();
+        let mut anchors: BTreeSet<&str> = BTreeSet::new();
+        if let Some(focus) = &view.focus {
+            let start = resolve(focus)?;
+            anchors.insert(start);
+            let reached = reach(&forward, [start], view.depth);
+            keep.retain(|n| reached.contains(n.as_str()));
+        }
+        if let Some(target) = &view.reaching {
+            let start = resolve(target)?;
+            anchors.insert(start);
+            let reached = reach(&backward, [start], view.depth);
+            keep.retain(|n| reached.contains(n.as_str()));
+        }
+        // Without a focus, the depth counts from the projects; with a scope
+        // filter, packages only the dropped requirements pulled in go away
+        if anchors.is_empty() && (view.depth.is_some() || !view.scopes.is_empty()) {
+            let projects = analyzer.projects.iter().map(|p| p.name.as_str());
+            let reached = reach(&forward, projects, view.depth);
+            keep.retain(|n| reached.contains(n.as_str()));
+        }
+
+        // Projects are not in any category and have no leaves to hide
+        let projects: BTreeSet<&str> = analyzer.projects.iter().map(|p| p.name.as_str()).collect();
+        if !view.categories.is_empty() {
+            let labels: BTreeMap<String, String> = analyzer
+                .graph_clusters()
+                .into_iter()
+                .flat_map(|(_, label, members)| {
+                    let graph = &analyzer.graph;
+                    members.into_iter().map(move |node| (graph[node].clone(), label.clone()))
+                })
+                .collect();
+            let selected = |label: &String| view.categories.iter().any(|c| c.eq_ignore_ascii_case(label));
+            keep.retain(|n| {
+                (projects.contains(n.as_str()) && !analyzer.local_packages.contains(n))
+                    || labels.get(n).is_some_and(selected)
+            });
+        }
+        if view.hide_leaves {
+            keep.retain(|n| {
+                anchors.contains(n.as_str())
+                    || projects.contains(n.as_str())
+                    || analyzer.local_packages.contains(n)
+                    || analyzer.node_map.get(n).is_some_and(|&node| {
+                        analyzer.graph.neighbors(node).next().is_some()
+                    })
+            });
+        }
+        keep
+    };
+
+    analyzer.graph = analyzer
+        .graph
+        .filter_map(|_, name| keep.contains(name).then(|| name.clone()), |_, _| Some(()));
+    // Records of the packages left keep their projects, so version counts
+    // and conflict colors stay as they were
+    analyzer.projects.retain(|p| keep.contains(&p.name));
+    analyzer.dependencies.retain(|name, _| keep.contains(name));
+    rebuild_node_map(analyzer);
+
+    if view.collapse_clusters {
+        collapse_clusters(analyzer);
+    }
+    Ok(())
+}
+
+/// `optional` and `group` match every `optional:<extra>` and `group:<name>`.
+fn scope_matches(filter: &str, scope: &str) -> bool {
+    scope == filter || scope.split_once(':').is_some_and(|(kind, _)| kind == filter)
+}
+
+/// Names reachable from `starts` in at most `max_depth` steps, starts included.
+fn reach<'a>(
+    adjacency: &BTreeMap<&'a str, Vec<&'a str>>,
+    starts: impl IntoIterator<Item = &'a str>,
+    max_depth: Option<usize>,
+) -> BTreeSet<&'a str> {
+    let mut reached = BTreeSet::new();
+    let mut queue = VecDeque::new();
+    for start in starts {
+        if reached.insert(start) {
+            queue.push_back((start, 0));
+        }
+    }
+    while let Some((name, depth)) = queue.pop_front() {
+        if max_depth.is_some_and(|max| depth >= max) {
+            continue;
+        }
+        for &next in adjacency.get(name).into_iter().flatten() {
+            if reached.insert(next) {
+                queue.push_back((next, depth + 1));
+            }
+        }
+    }
+    reached
+}
+
+/// Replace the packages of every cluster with a single node named after
+/// the cluster. Dependency records keep their package names; `collapsed`
+/// maps each of them to the node that stands in for it.
+fn collapse_clusters(analyzer: &mut DependencyAnalyzer) {
+    let mut graph = petgraph::graph::DiGraph::new();
+    let mut nodes = BTreeMap::new();
+    for (_, label, members) in analyzer.graph_clusters() {
+        if members.is_empty() {
+            continue;
+        }
+        nodes.insert(label.clone(), graph.add_node(label.clone()));
+        for node in members {
+            analyzer.collapsed.insert(analyzer.graph[node].clone(), label.clone());
+        }
+    }
+    let mut edges = BTreeSet::new();
+    for (from, to) in analyzer.sorted_edges() {
+        let from = &analyzer.collapsed[&analyzer.graph[from]];
+        let to = &analyzer.collapsed[&analyzer.graph[to]];
+        if from != to && edges.insert((from.clone(), to.clone())) {
+            graph.add_edge(nodes[from], nodes[to], ());
+        }
+    }
+    analyzer.graph = graph;
+    rebuild_node_map(analyzer);
+}
+
+fn rebuild_node_map(analyzer: &mut DependencyAnalyzer) {
+    analyzer.node_map = analyzer
+        .graph
+        .node_indices()
+        .map(|node| (analyzer.graph[node].clone(), node))
+        .collect();
+}
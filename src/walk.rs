@@ -0,0 +1,198 @@
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::WalkBuilder;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::vfs::{FileSystem, GitTree};
+
+/// File names the analyzer knows how to parse.
+pub const MANIFEST_NAMES: &[&str] = &[
+    "requirements.txt",
+    "setup.py",
+    "setup.cfg",
+    "Pipfile",
+    "pyproject.toml",
+    "environment.yml",
+];
+
+/// Directories that never contain first-party manifests: VCS metadata,
+/// virtualenvs, tool caches, build output and vendored third-party code.
+pub const DEFAULT_EXCLUDED_DIRS: &[&str] = &[
+    ".git",
+    ".hg",
+    ".svn",
+    ".venv",
+    "venv",
+    ".tox",
+    ".nox",
+    "node_modules",
+    "__pycache__",
+    ".mypy_cache",
+    ".pytest_cache",
+    ".ruff_cache",
+    ".eggs",
+    "build",
+    "dist",
+    "site-packages",
+    "_vendor",
+];
+
+/// Something found while walking the scan root.
+pub enum WalkEvent {
+    Manifest(PathBuf),
+    Error { path: PathBuf, message: String },
+}
+
+pub fn build_globset(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).with_context(|| format!("Invalid glob '{}'", pattern))?);
+    }
+    Ok(builder.build()?)
+}
+
+/// Excludes use gitignore semantics so that `legacy/` or `legacy/**` prune
+/// the whole directory instead of testing every file below it.
+fn build_excludes(root: &Path, config: &Config) -> Result<Override> {
+    let mut overrides = OverrideBuilder::new(root);
+    for pattern in &config.exclude {
+        overrides
+            .add(&format!("!{}", pattern))
+            .with_context(|| format!("Invalid exclude glob '{}'", pattern))?;
+    }
+    Ok(overrides.build()?)
+}
+
+/// Collect the dependency files below `root` to parse, honoring the default
+/// excludes, the configured include/exclude globs and the depth limit.
+/// Entries are visited in file name order, so the result does not depend on
+/// the file system.
+pub fn find_manifests(root: &Path, config: &Config, files: &FileSystem) -> Result<Vec<WalkEvent>> {
+    match files {
+        FileSystem::Disk => walk_disk(root, config),
+        FileSystem::Git(tree) => walk_tree(root, config, tree, files),
+    }
+}
+
+/// Walk the working tree, also honoring .gitignore/.ignore files.
+fn walk_disk(root: &Path, config: &Config) -> Result<Vec<WalkEvent>> {
+    let include = build_globset(&config.include)?;
+
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .follow_links(config.follow_links)
+        .max_depth(config.max_depth)
+        .hidden(false)
+        .parents(config.respect_gitignore)
+        .ignore(config.respect_gitignore)
+        .git_ignore(config.respect_gitignore)
+        .git_global(config.respect_gitignore)
+        .git_exclude(config.respect_gitignore)
+        .require_git(false)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .overrides(build_excludes(root, config)?);
+    if config.default_excludes {
+        builder.filter_entry(|entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            !is_dir || entry.depth() == 0 || !is_default_excluded(entry.path(), &FileSystem::Disk)
+        });
+    }
+
+    let mut events = Vec::new();
+    // The same file can be reachable through several symlinks
+    let mut seen = HashSet::new();
+    for entry in builder.build() {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                events.push(WalkEvent::Error {
+                    path: error_path(&e).unwrap_or_else(|| root.to_path_buf()),
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let file_name = entry.file_name().to_string_lossy();
+        if !MANIFEST_NAMES.contains(&file_name.as_ref()) {
+            continue;
+        }
+
+        let relative = crate::relative_path(&entry.path().display().to_string(), root);
+        if !config.include.is_empty() && !include.is_match(&relative) {
+            continue;
+        }
+        let canonical = entry
+            .path()
+            .canonicalize()
+            .unwrap_or_else(|_| entry.path().to_path_buf());
+        if seen.insert(canonical) {
+            events.push(WalkEvent::Manifest(entry.into_path()));
+        }
+    }
+    Ok(events)
+}
+
+/// List the files of a git revision. Everything in it is tracked, so
+/// ignore files do not apply, and symlinks are not followed.
+fn walk_tree(
+    root: &Path,
+    config: &Config,
+    tree: &GitTree,
+    files: &FileSystem,
+) -> Result<Vec<WalkEvent>> {
+    let include = build_globset(&config.include)?;
+    let excludes = build_excludes(root, config)?;
+
+    let mut events = Vec::new();
+    for path in tree.paths() {
+        if config.max_depth.is_some_and(|max| path.components().count() > max) {
+            continue;
+        }
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        if !MANIFEST_NAMES.contains(&file_name.as_ref()) {
+            continue;
+        }
+        let pruned = path.ancestors().skip(1).any(|dir| {
+            !dir.as_os_str().is_empty()
+                && ((config.default_excludes && is_default_excluded(&root.join(dir), files))
+                    || excludes.matched(dir, true).is_ignore())
+        });
+        if pruned || excludes.matched(path, false).is_ignore() {
+            continue;
+        }
+        let relative = crate::relative_path(&path.display().to_string(), Path::new(""));
+        if !config.include.is_empty() && !include.is_match(&relative) {
+            continue;
+        }
+        events.push(WalkEvent::Manifest(root.join(path)));
+    }
+    Ok(events)
+}
+
+fn is_default_excluded(dir: &Path, files: &FileSystem) -> bool {
+    let name = dir
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+    DEFAULT_EXCLUDED_DIRS.contains(&name.as_ref())
+        || name.ends_with(".egg-info")
+        // Virtualenvs with unusual names still carry this marker file
+        || files.is_file(&dir.join("pyvenv.cfg"))
+}
+
+fn error_path(error: &ignore::Error) -> Option<PathBuf> {
+    match error {
+        ignore::Error::WithPath { path, .. } => Some(path.clone()),
+        ignore::Error::Loop { child, .. } => Some(child.clone()),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            error_path(err)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,226 @@
+//! `why` query: every path from a project through its declared requirements
+//! to a given package, with the manifest line and constraint of each step.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use crate::lockfile::normalize_name;
+use crate::report::LocationEntry;
+use crate::tree::{display_spec, RequirementGraph};
+use crate::DependencyAnalyzer;
+
+#[derive(Debug, Serialize)]
+pub struct WhyReport {
+    pub package: String,
+    pub paths: Vec<DependencyPath>,
+    /// More paths exist than the limit allowed to list
+    pub truncated: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DependencyPath {
+    /// Id of the project the path starts at
+    pub project: String,
+    pub steps: Vec<PathStep>,
+}
+
+/// One edge of a path: `from` requires `to`.
+#[derive(Debug, Serialize)]
+pub struct PathStep {
+    pub from: String,
+    pub to: String,
+    /// Manifest entries declaring the requirement; empty for dependencies
+    /// the analyzer knows about without a manifest spelling them out
+    pub requirements: Vec<StepRequirement>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StepRequirement {
+    /// Version constraint as written, empty if unconstrained
+    pub constraint: String,
+    pub scope: String,
+    pub location: LocationEntry,
+}
+
+/// Find the paths from a project to `package`, at most `max_paths` of them.
+/// Names are compared after PEP 503 normalization.
+pub fn build_report(
+    analyzer: &DependencyAnalyzer,
+    package: &str,
+    max_paths: usize,
+) -> Result<WhyReport> {
+    let graph = RequirementGraph::build(analyzer);
+    let wanted = normalize_name(package);
+    let Some(target) = graph.nodes.iter().find(|n| normalize_name(n) == wanted) else {
+        anyhow::bail!("Package '{}' is not in the dependency graph", package);
+    };
+
+    let mut parents: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (dependent, dependency) in &graph.edges {
+        parents.entry(dependency).or_default().push(dependent);
+    }
+    let projects: BTreeMap<&str, &str> = analyzer
+        .projects
+        .iter()
+        .map(|p| (p.name.as_str(), p.id.as_str()))
+        .collect();
+
+    // Only packages some project depends on can be on a path; skipping the
+    // rest keeps the walk from exploring dead ends
+    let mut children: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (dependent, dependency) in &graph.edges {
+        children.entry(dependent).or_default().push(dependency);
+    }
+    let mut reachable: BTreeSet<&str> = BTreeSet::new();
+    let mut queue: Vec<&str> = projects.keys().copied().collect();
+    while let Some(node) = queue.pop() {
+        if reachable.insert(node) {
+            queue.extend(children.get(node).into_iter().flatten());
+        }
+    }
+
+    // Walk up from the target; every project on the way starts a path. The
+    // number of simple paths can grow exponentially, so stop at the limit
+    let mut chains: Vec<Vec<&str>> = Vec::new();
+    let mut truncated = false;
+    let mut stack: Vec<Vec<&str>> = vec![vec![target.as_str()]];
+    while let Some(chain) = stack.pop() {
+        let top = chain[chain.len() - 1];
+        if chain.len() > 1 && projects.contains_key(top) {
+            if chains.len() == max_paths {
+                truncated = true;
+                break;
+            }
+            chains.push(chain.clone());
+        }
+        for &parent in parents.get(top).into_iter().flatten().rev() {
+            // Simple paths only, so cycles end the walk
+            if reachable.contains(parent) && !chain.contains(&parent) {
+                let mut longer = chain.clone();
+                longer.push(parent);
+                stack.push(longer);
+            }
+        }
+    }
+
+    let mut paths: Vec<DependencyPath> = chains
+        .into_iter()
+        .map(|mut chain| {
+            chain.reverse();
+            let steps = chain
+                .windows(2)
+                .map(|pair| step(analyzer, &graph, pair[0], pair[1]))
+                .collect();
+            DependencyPath {
+                project: projects[chain[0]].to_string(),
+                steps,
+            }
+        })
+        .collect();
+    paths.sort_by(|a, b| {
+        let key = |p: &DependencyPath| {
+            (p.project.clone(), p.steps.iter().map(|s| s.to.clone()).collect::<Vec<_>>())
+        };
+        key(a).cmp(&key(b))
+    });
+
+    Ok(WhyReport {
+        package: target.clone(),
+        paths,
+        truncated,
+    })
+}
+
+fn step(analyzer: &DependencyAnalyzer, graph: &RequirementGraph, from: &str, to: &str) -> PathStep {
+    let requirements = graph
+        .requirements
+        .get(&(from.to_string(), to.to_string()))
+        .into_iter()
+        .flatten()
+        .map(|dep| StepRequirement {
+            constraint: display_spec(dep).to_string(),
+            scope: dep.scope.clone(),
+            location: LocationEntry::new(&dep.source_file, dep.span, analyzer),
+        })
+        .collect();
+    PathStep {
+        from: from.to_string(),
+        to: to.to_string(),
+        requirements,
+    }
+}
+
+impl WhyReport {
+    pub fn render_text(&self) -> String {
+        if self.paths.is_empty() {
+            return format!("No project depends on {}.\n", self.package);
+        }
+        let mut output = if self.truncated {
+            format!(
+                "{} is required through more than {} path(s) (raise --max-paths to see more):\n",
+                self.package,
+                self.paths.len()
+            )
+        } else {
+            format!("{} is required through {} path(s):\n", self.package, self.paths.len())
+        };
+        for path in &self.paths {
+            let mut chain = format!("{} ({})", path.steps[0].from, path.project);
+            for step in &path.steps {
+                let _ = write!(chain, " -> {}", step.to);
+            }
+            let _ = writeln!(output, "\n{}", chain);
+            for step in &path.steps {
+                if step.requirements.is_empty() {
+                    let _ = writeln!(output, "    {}: known dependency of {}", step.to, step.from);
+                    continue;
+                }
+                let declared: Vec<String> = step
+                    .requirements
+                    .iter()
+                    .map(|r| {
+                        let mut text = if r.constraint.is_empty() {
+                            "any version".to_string()
+                        } else {
+                            r.constraint.clone()
+                        };
+                        if r.scope != "main" {
+                            let _ = write!(text, " <{}>", r.scope);
+                        }
+                        let _ = write!(text, " at {}", format_location(&r.location));
+                        text
+                    })
+                    .collect();
+                let _ = writeln!(output, "    {}: {}", step.to, declared.join("; "));
+            }
+        }
+
+        // The constraints a resolver has to satisfy for the package itself
+        let constraints: BTreeSet<&str> = self
+            .paths
+            .iter()
+            .filter_map(|p| p.steps.last())
+            .flat_map(|s| &s.requirements)
+            .map(|r| r.constraint.as_str())
+            .filter(|c| !c.is_empty())
+            .collect();
+        if !constraints.is_empty() {
+            let _ = writeln!(
+                output,
+                "\nConstraints on {}: {}",
+                self.package,
+                constraints.into_iter().collect::<Vec<_>>().join("; ")
+            );
+        }
+        output
+    }
+}
+
+fn format_location(location: &LocationEntry) -> String {
+    match location.line {
+        Some(line) => format!("{}:{}", location.file, line),
+        None => location.file.clone(),
+    }
+}